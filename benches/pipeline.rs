@@ -0,0 +1,268 @@
+//! Throughput of each stage and of the whole edge detection on synthetic images.
+//!
+//! Runs on the first adapter wgpu finds, which may be a software adapter. The sizes can be chosen
+//! with arguments, for example `cargo bench --bench pipeline -- 512 2048`.
+use std::time::{Duration, Instant};
+
+use pollster::FutureExt;
+use wgpu_canny_edge_detection::{
+    CannyParams, EdgeMasks, Pipelines, Profiler, Renderer, Roi, Timer, apply_canny,
+    apply_double_thresholding, apply_edge_tracking, apply_gaussian_filter, apply_grayscale,
+    apply_magnitude_and_angle, apply_non_maximum_suppression, apply_sobel_operators,
+    create_rgba_texture, synthetic,
+};
+
+const SIZES: [u32; 5] = [512, 1024, 2048, 4096, 8192];
+
+/// Each stage is repeated until it took this long in total (but at most `MAX_ITERATIONS` times).
+const MIN_TIME: Duration = Duration::from_secs(1);
+const MAX_ITERATIONS: u32 = 20;
+
+struct BenchRenderer {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    pipelines: Pipelines,
+    profiler: Profiler,
+}
+
+impl BenchRenderer {
+    fn new() -> Self {
+        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::from_env_or_default());
+
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions::default())
+            .block_on()
+            .or_else(|_| {
+                instance
+                    .request_adapter(&wgpu::RequestAdapterOptions {
+                        force_fallback_adapter: true,
+                        ..Default::default()
+                    })
+                    .block_on()
+            })
+            .expect("Get adapter");
+
+        let info = adapter.get_info();
+        println!("Adapter: {} ({:?})", info.name, info.backend);
+
+        let (device, queue) = adapter
+            .request_device(&wgpu::DeviceDescriptor {
+                required_features: adapter.features() & wgpu::Features::TIMESTAMP_QUERY,
+                // the textures of the biggest images
+                required_limits: adapter.limits(),
+                ..Default::default()
+            })
+            .block_on()
+            .expect("Get device");
+
+        let profiler = Profiler::new(&device);
+        Self {
+            device,
+            queue,
+            pipelines: Pipelines::default(),
+            profiler,
+        }
+    }
+
+    /// Applies `stage` until enough time passed and returns the mean duration.
+    fn measure(&self, mut stage: impl FnMut()) -> Duration {
+        // compiles the pipelines and leaves out the first, slower run
+        stage();
+        self.profiler.finish(self);
+
+        let mut total = Duration::ZERO;
+        let mut iterations = 0;
+        while total < MIN_TIME && iterations < MAX_ITERATIONS {
+            stage();
+            total += self.profiler.finish(self).total();
+            iterations += 1;
+        }
+
+        total / iterations
+    }
+}
+
+impl Renderer for BenchRenderer {
+    fn device(&self) -> &wgpu::Device {
+        &self.device
+    }
+
+    fn queue(&self) -> &wgpu::Queue {
+        &self.queue
+    }
+
+    fn pipelines(&self) -> Option<&Pipelines> {
+        Some(&self.pipelines)
+    }
+
+    fn profiler(&self) -> Option<&Profiler> {
+        Some(&self.profiler)
+    }
+}
+
+fn main() {
+    // `cargo bench` passes `--bench`
+    let sizes: Vec<u32> = std::env::args()
+        .skip(1)
+        .filter_map(|arg| arg.parse().ok())
+        .collect();
+    let sizes = if sizes.is_empty() {
+        SIZES.to_vec()
+    } else {
+        sizes
+    };
+
+    let renderer = BenchRenderer::new();
+    match renderer.profiler.timer() {
+        Timer::GpuTimestamps => println!("Timer: GPU timestamps"),
+        Timer::CpuWallClock => println!("Timer: wall clock"),
+    }
+    println!();
+    println!(
+        "{:<24}  {:>10}  {:>12}  {:>10}",
+        "Stage", "Size", "Time (ms)", "MP/s"
+    );
+
+    let max_texture_dimension = renderer.device.limits().max_texture_dimension_2d;
+    for size in sizes {
+        if size > max_texture_dimension {
+            println!("Skipping {size}²: bigger than the texture limit of {max_texture_dimension}");
+            continue;
+        }
+
+        bench_size(&renderer, size);
+    }
+}
+
+fn bench_size(renderer: &BenchRenderer, size: u32) {
+    let params = CannyParams::default();
+    let megapixels = (size as f64 * size as f64) / 1e6;
+    let report = |stage: &str, duration: Duration| {
+        println!(
+            "{stage:<24}  {:>10}  {:>12.3}  {:>10.1}",
+            format!("{size}²"),
+            duration.as_secs_f64() * 1000.,
+            megapixels / duration.as_secs_f64()
+        );
+    };
+
+    let position = size as f32 / 2. + 0.3;
+    let img =
+        synthetic::circle(size, size, [position, position], size as f32 / 3.).with_noise(8., 1);
+    let input = create_rgba_texture(renderer, &img.image);
+
+    // each stage gets the output of the previous one, which is dropped as soon as possible to keep
+    // the memory usage of the big images down
+    report(
+        "gray scale",
+        renderer.measure(|| {
+            apply_grayscale(renderer, view(&input), None).unwrap();
+        }),
+    );
+    let gray_scale = apply_grayscale(renderer, view(&input), None).unwrap();
+
+    let gaussian_filter = || {
+        apply_gaussian_filter(
+            renderer,
+            view(&gray_scale),
+            params.sigma,
+            params.kernel_radius,
+            None,
+        )
+    };
+    report(
+        "gaussian",
+        renderer.measure(|| {
+            gaussian_filter();
+        }),
+    );
+    let gaussian = gaussian_filter();
+    drop(gray_scale);
+
+    let sobel_operators =
+        || apply_sobel_operators(renderer, view(&gaussian), params.gradient_operator, None);
+    report(
+        "gradients",
+        renderer.measure(|| {
+            sobel_operators();
+        }),
+    );
+    let (horizontal, vertical) = sobel_operators();
+    drop(gaussian);
+
+    let magnitude_and_angle =
+        || apply_magnitude_and_angle(renderer, view(&vertical), view(&horizontal), None);
+    report(
+        "magnitude",
+        renderer.measure(|| {
+            magnitude_and_angle();
+        }),
+    );
+    let (magnitudes, radians) = magnitude_and_angle();
+    drop((horizontal, vertical));
+
+    let non_maximum_suppression = || {
+        apply_non_maximum_suppression(
+            renderer,
+            view(&magnitudes),
+            view(&radians),
+            params.nms_mode,
+            None,
+        )
+    };
+    report(
+        "non maximum suppression",
+        renderer.measure(|| {
+            non_maximum_suppression();
+        }),
+    );
+    let non_maximum_suppression = non_maximum_suppression();
+    drop((magnitudes, radians));
+
+    let double_thresholding = || {
+        apply_double_thresholding(
+            renderer,
+            view(&non_maximum_suppression),
+            params.thresholds,
+            None,
+            &EdgeMasks::default(),
+            None,
+        )
+    };
+    report(
+        "double thresholding",
+        renderer.measure(|| {
+            double_thresholding();
+        }),
+    );
+    let double_thresholding = double_thresholding();
+    drop(non_maximum_suppression);
+
+    // edge tracking works in place, so the ROI makes it work on a fresh copy each time
+    let whole_image = Roi::new(0, 0, size, size);
+    report(
+        "edge tracking",
+        renderer.measure(|| {
+            apply_edge_tracking(renderer, view(&double_thresholding), Some(whole_image));
+        }),
+    );
+    drop(double_thresholding);
+
+    report(
+        "canny",
+        renderer.measure(|| {
+            apply_canny(renderer, view(&input), &params, None, &EdgeMasks::default()).unwrap();
+        }),
+    );
+
+    // the sum of the stages leaves out the time between them
+    let start = Instant::now();
+    apply_canny(renderer, view(&input), &params, None, &EdgeMasks::default()).unwrap();
+    renderer.device.poll(wgpu::PollType::Wait).unwrap();
+    report("canny (wall clock)", start.elapsed());
+    renderer.profiler.finish(renderer);
+}
+
+fn view(texture: &wgpu::Texture) -> wgpu::TextureView {
+    texture.create_view(&wgpu::TextureViewDescriptor::default())
+}
@@ -0,0 +1,298 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, mpsc},
+    time::{Duration, Instant},
+};
+
+use image::{DynamicImage, ImageReader};
+use pollster::FutureExt;
+use wgpu_canny_edge_detection::{
+    CannyParams, EdgeMasks, OutputEncoding, apply_canny, apply_output_encoding,
+    create_image_texture, read_texture,
+};
+
+use crate::{Renderer, cli::OutputArgs};
+
+struct Decoded {
+    path: PathBuf,
+    output_path: PathBuf,
+    image: Result<DynamicImage, String>,
+    decode_time: Duration,
+}
+
+struct Encode {
+    path: PathBuf,
+    output_path: PathBuf,
+    /// The encoded values of the edge map.
+    values: Vec<f32>,
+    width: u32,
+    height: u32,
+    decode_time: Duration,
+    gpu_time: Duration,
+}
+
+struct FileReport {
+    path: PathBuf,
+    decode_time: Duration,
+    gpu_time: Duration,
+    encode_time: Duration,
+    error: Option<String>,
+}
+
+/// Applies the edge detection to every file of `input_dir` whose name matches `pattern` and
+/// writes the final edge maps to `output_dir`.
+///
+/// Decoding and encoding happen on worker threads while the GPU processes the current image.
+pub fn run(
+    renderer: &Renderer,
+    input_dir: &Path,
+    output_dir: &Path,
+    pattern: &glob::Pattern,
+    params: &CannyParams,
+    output: OutputArgs,
+) {
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(input_dir)
+        .expect("Read input dir")
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file())
+        .filter(|path| {
+            path.file_name()
+                .is_some_and(|name| pattern.matches(&name.to_string_lossy()))
+        })
+        .collect();
+    paths.sort();
+
+    let output_paths = output_paths(&paths, output_dir, output.format.extension());
+    std::fs::create_dir_all(output_dir).expect("Create output dir");
+
+    println!("Processing {} files...", paths.len());
+
+    let workers = std::thread::available_parallelism().map_or(1, |amount| amount.get());
+    let start = Instant::now();
+
+    let encoding = output.encoding();
+    let paths = Arc::new(Mutex::new(paths.into_iter().zip(output_paths)));
+    let (decoded_tx, decoded_rx) = mpsc::sync_channel::<Decoded>(workers);
+    let (encode_tx, encode_rx) = mpsc::sync_channel::<Encode>(workers);
+    let encode_rx = Arc::new(Mutex::new(encode_rx));
+    let (report_tx, report_rx) = mpsc::channel::<FileReport>();
+
+    std::thread::scope(|scope| {
+        for _ in 0..workers {
+            let paths = paths.clone();
+            let decoded_tx = decoded_tx.clone();
+
+            scope.spawn(move || {
+                loop {
+                    let Some((path, output_path)) = paths.lock().unwrap().next() else {
+                        break;
+                    };
+
+                    let decode_start = Instant::now();
+                    let image = ImageReader::open(&path)
+                        .map_err(|err| err.to_string())
+                        .and_then(|reader| reader.decode().map_err(|err| err.to_string()));
+
+                    let decoded = Decoded {
+                        path,
+                        output_path,
+                        image,
+                        decode_time: decode_start.elapsed(),
+                    };
+                    if decoded_tx.send(decoded).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(decoded_tx);
+
+        for _ in 0..workers {
+            let encode_rx = encode_rx.clone();
+            let report_tx = report_tx.clone();
+
+            scope.spawn(move || {
+                loop {
+                    let Ok(job) = encode_rx.lock().unwrap().recv() else {
+                        break;
+                    };
+
+                    let encode_start = Instant::now();
+                    let error = output
+                        .save(&job.output_path, job.values, job.width, job.height)
+                        .err();
+
+                    report_tx
+                        .send(FileReport {
+                            path: job.path,
+                            decode_time: job.decode_time,
+                            gpu_time: job.gpu_time,
+                            encode_time: encode_start.elapsed(),
+                            error,
+                        })
+                        .unwrap();
+                }
+            });
+        }
+
+        // the GPU work happens on this thread, one image after another
+        for decoded in decoded_rx {
+            let result = decoded.image.and_then(|image| {
+                let gpu_start = Instant::now();
+                detect_edges(renderer, &image, params, &encoding)
+                    .map(|values| (values, image.width(), image.height(), gpu_start.elapsed()))
+            });
+
+            match result {
+                Ok((values, width, height, gpu_time)) => {
+                    encode_tx
+                        .send(Encode {
+                            path: decoded.path,
+                            output_path: decoded.output_path,
+                            values,
+                            width,
+                            height,
+                            decode_time: decoded.decode_time,
+                            gpu_time,
+                        })
+                        .unwrap();
+                }
+                Err(error) => report_tx
+                    .send(FileReport {
+                        path: decoded.path,
+                        decode_time: decoded.decode_time,
+                        gpu_time: Duration::ZERO,
+                        encode_time: Duration::ZERO,
+                        error: Some(error),
+                    })
+                    .unwrap(),
+            }
+        }
+        drop(encode_tx);
+    });
+    drop(report_tx);
+
+    let mut reports: Vec<FileReport> = report_rx.into_iter().collect();
+    reports.sort_by(|a, b| a.path.cmp(&b.path));
+
+    print_summary(&reports, start.elapsed());
+}
+
+/// The output file of each input, named after the input without its extension.
+///
+/// Inputs whose names only differ in their extension (`a.jpg` and `a.png`) keep it, as `a_jpg`
+/// and `a_png`, so they don't overwrite each other.
+fn output_paths(paths: &[PathBuf], output_dir: &Path, extension: &str) -> Vec<PathBuf> {
+    let mut stem_counts = HashMap::new();
+    for path in paths {
+        *stem_counts.entry(path.file_stem()).or_insert(0) += 1;
+    }
+
+    paths
+        .iter()
+        .map(|path| {
+            let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+            let name = match path.extension() {
+                Some(input_extension) if stem_counts[&path.file_stem()] > 1 => {
+                    format!("{stem}_{}", input_extension.to_string_lossy())
+                }
+                _ => stem.into_owned(),
+            };
+
+            output_dir.join(name).with_extension(extension)
+        })
+        .collect()
+}
+
+/// Applies the edge detection and encodes the final edge map on the GPU, like `detect` does.
+///
+/// Images which are too big for a single texture are processed in tiles and encoded on the CPU.
+fn detect_edges(
+    renderer: &Renderer,
+    image: &DynamicImage,
+    params: &CannyParams,
+    encoding: &OutputEncoding,
+) -> Result<Vec<f32>, String> {
+    let device = &renderer.device;
+    if image.width() == 0 || image.height() == 0 {
+        return Err("The image is empty".to_string());
+    }
+
+    if renderer.needs_tiles(image) {
+        return renderer
+            .detect_tiled(image, params, encoding)
+            .map_err(|err| err.to_string());
+    }
+
+    device.push_error_scope(wgpu::ErrorFilter::OutOfMemory);
+    device.push_error_scope(wgpu::ErrorFilter::Validation);
+
+    let input_texture = create_image_texture(renderer, image);
+    let encoded = apply_canny(
+        renderer,
+        input_texture.create_view(&wgpu::TextureViewDescriptor::default()),
+        params,
+        None,
+        &EdgeMasks::default(),
+    )
+    .map(|edges| {
+        apply_output_encoding(
+            renderer,
+            edges.create_view(&wgpu::TextureViewDescriptor::default()),
+            encoding,
+        )
+    });
+
+    // a failed stage leaves invalid textures behind, which can't be read back
+    let validation_error = device.pop_error_scope().block_on();
+    let out_of_memory_error = device.pop_error_scope().block_on();
+    if let Some(err) = validation_error.or(out_of_memory_error) {
+        return Err(err.to_string());
+    }
+
+    let encoded = encoded.map_err(|err| err.to_string())?;
+    Ok(read_texture(renderer, &encoded))
+}
+
+fn print_summary(reports: &[FileReport], total_time: Duration) {
+    let name_width = reports
+        .iter()
+        .map(|report| report.path.display().to_string().len())
+        .max()
+        .unwrap_or(0)
+        .max("File".len());
+
+    println!();
+    println!(
+        "{:<name_width$}  {:>10}  {:>10}  {:>10}  Status",
+        "File", "Decode", "GPU", "Encode"
+    );
+
+    for report in reports {
+        let status = match &report.error {
+            Some(error) => format!("FAILED: {error}"),
+            None => "OK".to_string(),
+        };
+
+        println!(
+            "{:<name_width$}  {:>8.1}ms  {:>8.1}ms  {:>8.1}ms  {status}",
+            report.path.display().to_string(),
+            report.decode_time.as_secs_f64() * 1000.,
+            report.gpu_time.as_secs_f64() * 1000.,
+            report.encode_time.as_secs_f64() * 1000.,
+        );
+    }
+
+    let failed = reports
+        .iter()
+        .filter(|report| report.error.is_some())
+        .count();
+    println!();
+    println!(
+        "{} files processed, {} failed, took {:.2}s",
+        reports.len(),
+        failed,
+        total_time.as_secs_f64()
+    );
+}
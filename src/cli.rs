@@ -0,0 +1,639 @@
+use std::path::{Path, PathBuf};
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use image::{DynamicImage, GrayImage, ImageBuffer};
+use wgpu_canny_edge_detection::{
+    CannyParams, GradientOperator, NmsMode, OutputEncoding, OverlayParams, Thresholds,
+    TransferFunction,
+    metrics::{self, EdgeMetrics},
+    npy,
+};
+
+#[derive(Debug, Parser)]
+#[command(version, about = "Canny edge detection on the GPU")]
+pub struct Cli {
+    #[command(flatten)]
+    pub adapter: AdapterArgs,
+
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Applies the edge detection to a single image and saves the stages to the output directory.
+    Detect {
+        /// Path to the image file.
+        input: PathBuf,
+        /// Path to the directory where the stages are saved.
+        output_dir: PathBuf,
+
+        /// Comma separated list of the stages which are saved, for example `gray,nms,final`.
+        #[arg(long, value_enum, value_delimiter = ',', default_value = "final")]
+        stages: Vec<Stage>,
+
+        /// Prints the GPU time of each stage. Uses timestamp queries if the adapter supports them,
+        /// the wall clock time otherwise.
+        #[arg(long)]
+        profile: bool,
+
+        #[command(flatten)]
+        canny: CannyArgs,
+
+        #[command(flatten)]
+        output: OutputArgs,
+
+        #[command(flatten)]
+        debug: DebugOutputArgs,
+    },
+
+    /// Applies the edge detection to every image of a directory and saves the final edge maps.
+    Batch {
+        /// Path to the directory with the images.
+        input_dir: PathBuf,
+        /// Path to the directory where the edge maps are saved.
+        output_dir: PathBuf,
+        /// Only files whose name matches this glob pattern are processed.
+        #[arg(long, default_value = "*")]
+        glob: glob::Pattern,
+
+        #[command(flatten)]
+        canny: CannyArgs,
+
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+
+    /// Applies each stage on the GPU and on the CPU and prints how much they differ.
+    Compare {
+        /// Path to the image file.
+        input: PathBuf,
+
+        #[command(flatten)]
+        canny: CannyArgs,
+    },
+
+    /// Compares an edge map with a ground truth edge mask and prints quality metrics.
+    Eval {
+        /// Path to the edge map, for example a saved `7_edge_tracking.png`.
+        predicted: PathBuf,
+        /// Path to the ground truth edge mask. Non-zero pixels are edges.
+        ground_truth: PathBuf,
+
+        #[command(flatten)]
+        metrics: MetricsArgs,
+    },
+
+    /// Runs the edge detection over a grid of parameters and writes the quality metrics of each
+    /// combination to a CSV file.
+    Sweep(SweepArgs),
+
+    /// Lists the available adapters.
+    Adapters,
+}
+
+#[derive(Debug, Args)]
+pub struct AdapterArgs {
+    /// The graphics backend to use. By default, Vulkan and GL are tried.
+    #[arg(long, global = true, value_enum)]
+    pub backend: Option<Backend>,
+
+    /// Which kind of adapter is preferred.
+    #[arg(long, global = true, value_enum, default_value_t = PowerPreference::HighPerformance)]
+    pub power_preference: PowerPreference,
+
+    /// Only use a software adapter, like lavapipe or llvmpipe. Useful on machines without a GPU.
+    #[arg(long, global = true)]
+    pub fallback_adapter: bool,
+
+    /// Index or (part of the) name of the adapter to use, as listed by `canny adapters`.
+    #[arg(long, global = true, conflicts_with_all = ["power_preference", "fallback_adapter"])]
+    pub adapter: Option<AdapterSelection>,
+}
+
+impl AdapterArgs {
+    pub fn backends(&self) -> wgpu::Backends {
+        self.backend.map_or(
+            wgpu::Backends::VULKAN | wgpu::Backends::GL,
+            wgpu::Backends::from,
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum AdapterSelection {
+    Index(usize),
+    Name(String),
+}
+
+impl std::str::FromStr for AdapterSelection {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.parse() {
+            Ok(index) => Self::Index(index),
+            Err(_) => Self::Name(s.to_string()),
+        })
+    }
+}
+
+impl std::fmt::Display for AdapterSelection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Index(index) => write!(f, "{index}"),
+            Self::Name(name) => write!(f, "{name}"),
+        }
+    }
+}
+
+impl AdapterSelection {
+    /// Returns if `info` belongs to the adapter at `index` of the adapter list.
+    pub fn matches(&self, index: usize, info: &wgpu::AdapterInfo) -> bool {
+        match self {
+            Self::Index(selected) => *selected == index,
+            Self::Name(name) => info.name.to_lowercase().contains(&name.to_lowercase()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum Backend {
+    Vulkan,
+    Gl,
+}
+
+impl From<Backend> for wgpu::Backends {
+    fn from(backend: Backend) -> Self {
+        match backend {
+            Backend::Vulkan => wgpu::Backends::VULKAN,
+            Backend::Gl => wgpu::Backends::GL,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum PowerPreference {
+    HighPerformance,
+    LowPower,
+}
+
+impl From<PowerPreference> for wgpu::PowerPreference {
+    fn from(power_preference: PowerPreference) -> Self {
+        match power_preference {
+            PowerPreference::HighPerformance => wgpu::PowerPreference::HighPerformance,
+            PowerPreference::LowPower => wgpu::PowerPreference::LowPower,
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct CannyArgs {
+    /// Standard deviation of the gaussian filter.
+    #[arg(long, default_value_t = CannyParams::default().sigma)]
+    pub sigma: f32,
+
+    /// Radius of the gaussian kernel.
+    #[arg(long, default_value_t = CannyParams::default().kernel_radius)]
+    pub kernel_radius: u32,
+
+    /// Pixels above `high threshold * strongest edge` are edges.
+    #[arg(long, default_value_t = Thresholds::default().high)]
+    pub high_threshold: f32,
+
+    /// Pixels above `low threshold * high threshold * strongest edge` are weak edges.
+    #[arg(long, default_value_t = Thresholds::default().low)]
+    pub low_threshold: f32,
+
+    /// Kernels to compute the gradients with.
+    #[arg(long, value_enum, default_value_t = GradientOperatorArg::Sobel)]
+    pub gradient_operator: GradientOperatorArg,
+
+    /// How the neighbours along the gradient are looked up in the non maximum suppression.
+    #[arg(long, value_enum, default_value_t = NmsModeArg::Interpolated)]
+    pub nms_mode: NmsModeArg,
+}
+
+impl From<&CannyArgs> for CannyParams {
+    fn from(args: &CannyArgs) -> Self {
+        Self {
+            sigma: args.sigma,
+            kernel_radius: args.kernel_radius,
+            gradient_operator: args.gradient_operator.into(),
+            nms_mode: args.nms_mode.into(),
+            thresholds: Thresholds {
+                high: args.high_threshold,
+                low: args.low_threshold,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum GradientOperatorArg {
+    Sobel,
+    Scharr,
+    Prewitt,
+}
+
+impl From<GradientOperatorArg> for GradientOperator {
+    fn from(arg: GradientOperatorArg) -> Self {
+        match arg {
+            GradientOperatorArg::Sobel => Self::Sobel,
+            GradientOperatorArg::Scharr => Self::Scharr,
+            GradientOperatorArg::Prewitt => Self::Prewitt,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum NmsModeArg {
+    Interpolated,
+    Quantized,
+}
+
+impl From<NmsModeArg> for NmsMode {
+    fn from(arg: NmsModeArg) -> Self {
+        match arg {
+            NmsModeArg::Interpolated => Self::Interpolated,
+            NmsModeArg::Quantized => Self::Quantized,
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct SweepArgs {
+    /// Path to the directory with the images.
+    pub input_dir: PathBuf,
+    /// Path to the directory with the ground truth edge masks. A mask has the same file name (without
+    /// the extension) as its image.
+    pub ground_truth_dir: PathBuf,
+
+    /// Path of the CSV file.
+    #[arg(long, default_value = "sweep.csv")]
+    pub output: PathBuf,
+
+    /// Only images whose name matches this glob pattern are used.
+    #[arg(long, default_value = "*")]
+    pub glob: glob::Pattern,
+
+    /// Comma separated standard deviations of the gaussian filter.
+    #[arg(long, value_delimiter = ',', default_value = "1,1.6,2.4")]
+    pub sigma: Vec<f32>,
+
+    /// Comma separated high thresholds.
+    #[arg(long, value_delimiter = ',', default_value = "0.1,0.2,0.3")]
+    pub high_threshold: Vec<f32>,
+
+    /// Comma separated low thresholds.
+    #[arg(long, value_delimiter = ',', default_value = "0.5,0.7")]
+    pub low_threshold: Vec<f32>,
+
+    /// Comma separated gradient operators.
+    #[arg(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        default_value = "sobel,scharr,prewitt"
+    )]
+    pub gradient_operator: Vec<GradientOperatorArg>,
+
+    /// Radius of the gaussian kernel.
+    #[arg(long, default_value_t = CannyParams::default().kernel_radius)]
+    pub kernel_radius: u32,
+
+    /// How the neighbours along the gradient are looked up in the non maximum suppression.
+    #[arg(long, value_enum, default_value_t = NmsModeArg::Interpolated)]
+    pub nms_mode: NmsModeArg,
+
+    #[command(flatten)]
+    pub metrics: MetricsArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct MetricsArgs {
+    /// Edges which are at most this many pixels apart count as the same edge.
+    #[arg(long, default_value_t = 1.5)]
+    pub tolerance: f32,
+
+    /// Ignores this many pixels along the border, where the filters create edges.
+    #[arg(long, default_value_t = 0)]
+    pub ignore_border: u32,
+}
+
+impl MetricsArgs {
+    /// Compares the edge maps without their borders.
+    pub fn evaluate(&self, predicted: &GrayImage, ground_truth: &GrayImage) -> EdgeMetrics {
+        let crop = |img: &GrayImage| {
+            let border = self
+                .ignore_border
+                .min(img.width() / 2)
+                .min(img.height() / 2);
+
+            image::imageops::crop_imm(
+                img,
+                border,
+                border,
+                img.width() - 2 * border,
+                img.height() - 2 * border,
+            )
+            .to_image()
+        };
+
+        metrics::evaluate(&crop(predicted), &crop(ground_truth), self.tolerance)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Args)]
+pub struct OutputArgs {
+    /// Image format of the saved files.
+    #[arg(long, value_enum, default_value_t = OutputFormat::Png)]
+    pub format: OutputFormat,
+    /// Bits per value of the saved files. 8 and 16 bit files (PNG, TIFF) store the encoded values
+    /// between 0 and 1 and 32 bit files (TIFF, EXR) store the floats as they are. NPY files always
+    /// store the floats.
+    #[arg(long, value_enum, default_value_t = BitDepth::Eight)]
+    pub bit_depth: BitDepth,
+
+    /// Transfer function which is applied to the values. Defaults to gamma for 8 bit files and to
+    /// linear otherwise.
+    #[arg(long, value_enum)]
+    pub transfer: Option<TransferFunctionArg>,
+    /// Stretches the values of each file, so its smallest value is 0 and its biggest 1.
+    #[arg(long)]
+    pub normalize: bool,
+    /// Saves the absolute values, for the signed gradients.
+    #[arg(long)]
+    pub absolute: bool,
+    /// Saves `1 - value`, for black edges on white.
+    #[arg(long)]
+    pub invert: bool,
+}
+
+impl OutputArgs {
+    /// Returns an error if the format can't store values with the bit depth.
+    pub fn validate(&self) -> Result<(), String> {
+        // NPY files always store the raw floats
+        if matches!(self.format, OutputFormat::Npy) {
+            return Ok(());
+        }
+
+        let supported = match self.bit_depth {
+            BitDepth::Eight => !matches!(self.format, OutputFormat::Exr),
+            BitDepth::Sixteen => matches!(self.format, OutputFormat::Png | OutputFormat::Tiff),
+            BitDepth::Float => matches!(self.format, OutputFormat::Tiff | OutputFormat::Exr),
+        };
+
+        if supported {
+            Ok(())
+        } else {
+            Err(format!(
+                "The format '{}' can't store {} bit values",
+                self.format.extension(),
+                self.bit_depth.bits()
+            ))
+        }
+    }
+
+    /// The encoding which is applied to the values of a stage before they are saved.
+    pub fn encoding(&self) -> OutputEncoding {
+        let default_transfer = match (self.format, self.bit_depth) {
+            (OutputFormat::Npy, _) | (_, BitDepth::Sixteen | BitDepth::Float) => {
+                TransferFunction::Linear
+            }
+            (_, BitDepth::Eight) => TransferFunction::Gamma,
+        };
+
+        OutputEncoding {
+            transfer: self.transfer.map_or(default_transfer, Into::into),
+            normalize: self.normalize,
+            absolute: self.absolute,
+            invert: self.invert,
+        }
+    }
+
+    /// The encoding of the stages in the sheet, which has 8 bits like the default output.
+    pub fn sheet_encoding(&self) -> OutputEncoding {
+        OutputEncoding {
+            transfer: self.transfer.map_or(TransferFunction::Gamma, Into::into),
+            ..self.encoding()
+        }
+    }
+
+    /// Saves the (encoded) values of a stage in the format and with the bit depth.
+    pub fn save(
+        &self,
+        path: &Path,
+        values: Vec<f32>,
+        width: u32,
+        height: u32,
+    ) -> Result<(), String> {
+        match self.format {
+            OutputFormat::Npy => {
+                npy::save(path, &values, width, height).map_err(|err| err.to_string())
+            }
+            _ => self
+                .encode(values, width, height)
+                .save(path)
+                .map_err(|err| err.to_string()),
+        }
+    }
+
+    /// Turns the values of a stage into an image with the bit depth.
+    fn encode(&self, values: Vec<f32>, width: u32, height: u32) -> DynamicImage {
+        match self.bit_depth {
+            BitDepth::Eight => DynamicImage::ImageLuma8(
+                GrayImage::from_raw(
+                    width,
+                    height,
+                    values
+                        .into_iter()
+                        .map(|value| (value.clamp(0., 1.) * u8::MAX as f32).round() as u8)
+                        .collect(),
+                )
+                .unwrap(),
+            ),
+            BitDepth::Sixteen => DynamicImage::ImageLuma16(
+                ImageBuffer::from_raw(
+                    width,
+                    height,
+                    values
+                        .into_iter()
+                        .map(|value| (value.clamp(0., 1.) * u16::MAX as f32).round() as u16)
+                        .collect(),
+                )
+                .unwrap(),
+            ),
+            // neither TIFF nor EXR can be written with a single float channel
+            BitDepth::Float => DynamicImage::ImageRgb32F(
+                ImageBuffer::from_raw(
+                    width,
+                    height,
+                    values.into_iter().flat_map(|value| [value; 3]).collect(),
+                )
+                .unwrap(),
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum TransferFunctionArg {
+    Linear,
+    /// `value^(1 / 2.2)`.
+    Gamma,
+    Srgb,
+}
+
+impl From<TransferFunctionArg> for TransferFunction {
+    fn from(arg: TransferFunctionArg) -> Self {
+        match arg {
+            TransferFunctionArg::Linear => Self::Linear,
+            TransferFunctionArg::Gamma => Self::Gamma,
+            TransferFunctionArg::Srgb => Self::Srgb,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum BitDepth {
+    #[value(name = "8")]
+    Eight,
+    #[value(name = "16")]
+    Sixteen,
+    #[value(name = "32")]
+    Float,
+}
+
+impl BitDepth {
+    fn bits(&self) -> u32 {
+        match self {
+            Self::Eight => 8,
+            Self::Sixteen => 16,
+            Self::Float => 32,
+        }
+    }
+}
+
+/// Outputs which show the stages for reviews, documentation and bug reports.
+#[derive(Debug, Args)]
+pub struct DebugOutputArgs {
+    /// Colour of the edges in `overlay.png` as `RRGGBB` or `RRGGBBAA` hex, optionally starting with
+    /// `#`. The alpha blends the colour with the image.
+    #[arg(long, default_value = "ff0000")]
+    pub overlay_color: Color,
+
+    /// Colour of the weak edges which were kept by the edge tracking.
+    #[arg(long, default_value = "ffff00")]
+    pub overlay_weak_color: Color,
+
+    /// Radius (in pixels) by which the edges are dilated in `overlay.png`.
+    #[arg(long, default_value_t = OverlayParams::default().dilation)]
+    pub overlay_dilation: u32,
+
+    /// Also renders every numbered stage with its label into this file: an animated GIF if the
+    /// name ends with `.gif`, a grid otherwise. Computes the stages up to the final edge map.
+    #[arg(long)]
+    pub sheet: Option<PathBuf>,
+
+    /// Amount of columns of the grid. By default, the grid is about as wide as high.
+    #[arg(long)]
+    pub sheet_columns: Option<u32>,
+}
+
+impl From<&DebugOutputArgs> for OverlayParams {
+    fn from(args: &DebugOutputArgs) -> Self {
+        Self {
+            strong_color: args.overlay_color.0,
+            weak_color: args.overlay_weak_color.0,
+            dilation: args.overlay_dilation,
+        }
+    }
+}
+
+/// An RGBA colour.
+#[derive(Debug, Clone, Copy)]
+pub struct Color(pub [u8; 4]);
+
+impl std::str::FromStr for Color {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hex = s.strip_prefix('#').unwrap_or(s);
+        if !matches!(hex.len(), 6 | 8) || !hex.is_ascii() {
+            return Err(format!("'{s}' isn't a RRGGBB or RRGGBBAA colour"));
+        }
+
+        let mut color = [u8::MAX; 4];
+        for (channel, digits) in color.iter_mut().zip(hex.as_bytes().chunks(2)) {
+            let digits = std::str::from_utf8(digits).unwrap();
+            *channel = u8::from_str_radix(digits, 16)
+                .map_err(|_| format!("'{s}' isn't a RRGGBB or RRGGBBAA colour"))?;
+        }
+
+        Ok(Self(color))
+    }
+}
+
+/// The stages in the order in which they are computed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub enum Stage {
+    /// Every stage.
+    All,
+    Gray,
+    Gaussian,
+    /// The horizontal and vertical gradients.
+    Gradients,
+    /// The gradient magnitudes and directions.
+    Magnitude,
+    /// The gradient directions as colours, brighter for stronger gradients, with a legend. Always
+    /// saved as PNG.
+    Direction,
+    Nms,
+    Threshold,
+    /// The final edge map.
+    Final,
+    /// The final edges drawn over the input image, saved as `overlay.png`.
+    Overlay,
+}
+
+impl Stage {
+    /// Returns if `self` is part of the selected `stages`.
+    pub fn is_selected(self, stages: &[Stage]) -> bool {
+        stages
+            .iter()
+            .any(|&stage| stage == Self::All || stage == self)
+    }
+
+    /// Returns the last stage which has to be computed for the selected `stages`.
+    pub fn last(stages: &[Stage]) -> Self {
+        if stages.contains(&Self::All) {
+            return Self::Overlay;
+        }
+
+        stages.iter().copied().max().unwrap_or(Self::Final)
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum OutputFormat {
+    Png,
+    Jpeg,
+    Bmp,
+    Tiff,
+    Exr,
+    /// NumPy array of the raw `float32` values.
+    Npy,
+}
+
+impl OutputFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Png => "png",
+            Self::Jpeg => "jpg",
+            Self::Bmp => "bmp",
+            Self::Tiff => "tiff",
+            Self::Exr => "exr",
+            Self::Npy => "npy",
+        }
+    }
+}
@@ -0,0 +1,608 @@
+//! CPU implementations of the stages to validate the GPU output.
+//!
+//! Each function mirrors the `apply_*` function (and shader) of the same name, including its quirks,
+//! so the results should only differ by floating point rounding.
+use image::{GrayImage, ImageBuffer, Luma, RgbaImage};
+
+use crate::{
+    CannyParams, EDGE_TRACKING_TILE_SIZE, GradientOperator, NmsMode, OutputEncoding, OverlayParams,
+    Renderer, Roi, Thresholds, TransferFunction, edge_tracking_rounds, gaussian_kernel,
+    read_texture, visualization::direction_color,
+};
+
+pub type GrayF32Image = ImageBuffer<Luma<f32>, Vec<f32>>;
+
+// `4294967295.` in the shaders, which is rounded to the next f32
+const U32_MAX: f32 = u32::MAX as f32;
+
+/// CPU counterpart of [`crate::EdgeMasks`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EdgeMasks<'a> {
+    /// Pixels with the value zero are never an edge.
+    pub ignore: Option<&'a GrayImage>,
+    /// Pixels with a non-zero value are always treated as strong edges.
+    pub seed: Option<&'a GrayImage>,
+    /// Position of the thresholded image inside of the masks.
+    pub origin: [u32; 2],
+}
+
+pub fn apply_grayscale(img: &RgbaImage, roi: Option<Roi>) -> GrayF32Image {
+    let roi = roi.unwrap_or(Roi::new(0, 0, img.width(), img.height()));
+
+    GrayF32Image::from_fn(roi.width, roi.height, |x, y| {
+        let [r, g, b, _] = img
+            .get_pixel(x + roi.x, y + roi.y)
+            .0
+            .map(|c| c as f32 / 255.);
+
+        Luma([0.3 * r + 0.59 * g + 0.11 * b])
+    })
+}
+
+pub fn apply_gaussian_filter(
+    img: &GrayF32Image,
+    sigma: f32,
+    kernel_radius: u32,
+    roi: Option<Roi>,
+) -> GrayF32Image {
+    convolve(img, &gaussian_kernel(sigma, kernel_radius), roi)
+}
+
+/// Returns the horizontal and vertical gradients.
+pub fn apply_sobel_operators(
+    img: &GrayF32Image,
+    operator: GradientOperator,
+    roi: Option<Roi>,
+) -> (GrayF32Image, GrayF32Image) {
+    let (vertical_kernel, horizontal_kernel) = operator.kernels();
+
+    (
+        convolve(img, &horizontal_kernel, roi),
+        convolve(img, &vertical_kernel, roi),
+    )
+}
+
+/// Mirrors `kernels.wgsl`: pixels whose kernel leaves the image stay zero and the sum is only
+/// clamped from above, because `clamp(0., 1., sum)` is `min(1., sum)`.
+fn convolve(img: &GrayF32Image, kernel: &[f32], roi: Option<Roi>) -> GrayF32Image {
+    let roi = roi.unwrap_or(Roi::new(0, 0, img.width(), img.height()));
+    let kernel_size = (kernel.len() as f32).sqrt().round() as i64;
+    let half_kernel_size = kernel_size / 2;
+    let (width, height) = (img.width() as i64, img.height() as i64);
+
+    GrayF32Image::from_fn(roi.width, roi.height, |x, y| {
+        let (cx, cy) = ((x + roi.x) as i64, (y + roi.y) as i64);
+
+        let mut sum = 0f32;
+        for kx in -half_kernel_size..=half_kernel_size {
+            for ky in -half_kernel_size..=half_kernel_size {
+                let (px, py) = (cx + kx, cy + ky);
+                if px < 0 || py < 0 || px >= width || py >= height {
+                    return Luma([0.]);
+                }
+
+                let weight = kernel
+                    [((kx + half_kernel_size) + kernel_size * (ky + half_kernel_size)) as usize];
+                sum += weight * img.get_pixel(px as u32, py as u32).0[0];
+            }
+        }
+
+        Luma([sum.min(1.)])
+    })
+}
+
+/// Returns the magnitudes and the directions (in radians) of the gradients.
+pub fn apply_magnitude_and_angle(
+    vertical: &GrayF32Image,
+    horizontal: &GrayF32Image,
+    roi: Option<Roi>,
+) -> (GrayF32Image, GrayF32Image) {
+    let roi = roi.unwrap_or(Roi::new(0, 0, vertical.width(), vertical.height()));
+
+    let mut magnitudes = GrayF32Image::new(roi.width, roi.height);
+    let mut radians = GrayF32Image::new(roi.width, roi.height);
+    for y in 0..roi.height {
+        for x in 0..roi.width {
+            let v = vertical.get_pixel(x + roi.x, y + roi.y).0[0];
+            let h = horizontal.get_pixel(x + roi.x, y + roi.y).0[0];
+
+            magnitudes.put_pixel(x, y, Luma([(v * v + h * h).sqrt()]));
+            radians.put_pixel(x, y, Luma([if v != 0. { v.atan2(h) } else { 0. }]));
+        }
+    }
+
+    (magnitudes, radians)
+}
+
+pub fn apply_non_maximum_suppression(
+    magnitudes: &GrayF32Image,
+    radians: &GrayF32Image,
+    mode: NmsMode,
+    roi: Option<Roi>,
+) -> GrayF32Image {
+    use std::f32::consts::PI;
+
+    // neighbours outside of the image suppress the pixel
+    const OUTSIDE: f32 = 1e10;
+
+    let roi = roi.unwrap_or(Roi::new(0, 0, magnitudes.width(), magnitudes.height()));
+    let (width, height) = (magnitudes.width(), magnitudes.height());
+    let load = |x: u32, y: u32| magnitudes.get_pixel(x, y).0[0];
+
+    let bilinear_interpolation = |px: f32, py: f32| {
+        // the shader converts to `vec2u`, which saturates negative values to zero
+        let (x, y) = (px.floor().max(0.) as u32, py.floor().max(0.) as u32);
+        if x + 1 >= width || y + 1 >= height {
+            return OUTSIDE;
+        }
+
+        let smoothstep = |t: f32| {
+            let t = t.clamp(0., 1.);
+            t * t * (3. - 2. * t)
+        };
+        let mix = |a: f32, b: f32, t: f32| a * (1. - t) + b * t;
+        let (sx, sy) = (smoothstep(px - px.floor()), smoothstep(py - py.floor()));
+
+        let m1 = mix(load(x, y), load(x + 1, y), sx);
+        let m2 = mix(load(x, y + 1), load(x + 1, y + 1), sx);
+        mix(m1, m2, sy)
+    };
+
+    let neighbour = |x: i64, y: i64| {
+        if x >= 0 && y >= 0 && x < width as i64 && y < height as i64 {
+            load(x as u32, y as u32)
+        } else {
+            OUTSIDE
+        }
+    };
+
+    GrayF32Image::from_fn(roi.width, roi.height, |x, y| {
+        let (cx, cy) = (x + roi.x, y + roi.y);
+        let magnitude = load(cx, cy);
+        let radian = radians.get_pixel(cx, cy).0[0];
+
+        let (m1, m2) = match mode {
+            NmsMode::Quantized => {
+                // `round` of WGSL rounds half to even
+                let quantized = (radian / (PI / 4.)).round_ties_even() * (PI / 4.);
+                let dx = quantized.cos().round_ties_even() as i64;
+                let dy = quantized.sin().round_ties_even() as i64;
+
+                let (cx, cy) = (cx as i64, cy as i64);
+                (neighbour(cx + dx, cy + dy), neighbour(cx - dx, cy - dy))
+            }
+            NmsMode::Interpolated => {
+                let (dx, dy) = (radian.cos(), radian.sin());
+                let (cx, cy) = (cx as f32, cy as f32);
+
+                (
+                    bilinear_interpolation(cx + dx, cy + dy),
+                    bilinear_interpolation(cx - dx, cy - dy),
+                )
+            }
+        };
+
+        if m1 < magnitude && m2 < magnitude {
+            Luma([magnitude])
+        } else {
+            Luma([0.])
+        }
+    })
+}
+
+/// Returns `1` for edges, `0.5` for weak edges and `0` otherwise.
+pub fn apply_double_thresholding(
+    non_maximum_suppression: &GrayF32Image,
+    thresholds: Thresholds,
+    roi: Option<Roi>,
+    masks: &EdgeMasks,
+    max_value: Option<f32>,
+) -> GrayF32Image {
+    let nms = non_maximum_suppression;
+    let roi = roi.unwrap_or(Roi::new(0, 0, nms.width(), nms.height()));
+
+    let mask_value = |mask: &GrayImage, x: u32, y: u32| {
+        mask.get_pixel(x + roi.x + masks.origin[0], y + roi.y + masks.origin[1])
+            .0[0]
+    };
+    let is_ignored = |x: u32, y: u32| masks.ignore.is_some_and(|mask| mask_value(mask, x, y) == 0);
+    let is_seed = |x: u32, y: u32| masks.seed.is_some_and(|mask| mask_value(mask, x, y) > 0);
+
+    // same encoding as on the GPU
+    let encoded_max_value = match max_value {
+        Some(value) => encode_max_value(value),
+        None => {
+            let mut max = 0u32;
+            for y in 0..roi.height {
+                for x in 0..roi.width {
+                    if !is_ignored(x, y) {
+                        let value = nms.get_pixel(x + roi.x, y + roi.y).0[0];
+                        max = max.max((value * U32_MAX).floor() as u32);
+                    }
+                }
+            }
+            max
+        }
+    };
+
+    let (upper, lower) = threshold_bounds(encoded_max_value, thresholds);
+
+    GrayF32Image::from_fn(roi.width, roi.height, |x, y| {
+        let presence = nms.get_pixel(x + roi.x, y + roi.y).0[0];
+
+        let mut value = if presence >= upper {
+            1.
+        } else if presence < lower {
+            0.
+        } else {
+            0.5
+        };
+
+        if is_seed(x, y) {
+            value = 1.;
+        }
+        if is_ignored(x, y) {
+            value = 0.;
+        }
+
+        Luma([value])
+    })
+}
+
+/// Encodes the strongest edge like `max_value.wgsl` does.
+pub(crate) fn encode_max_value(value: f32) -> u32 {
+    (value * U32_MAX) as u32
+}
+
+/// The bounds of the edges and the weak edges for the (encoded) strongest edge, like in the shader.
+pub(crate) fn threshold_bounds(encoded_max_value: u32, thresholds: Thresholds) -> (f32, f32) {
+    let upper = (encoded_max_value as f32 * thresholds.high) / U32_MAX;
+
+    (upper, upper * thresholds.low)
+}
+
+/// Promotes every weak edge which is (8-)connected to an edge to an edge.
+///
+/// Like the shader, this runs in rounds of up to `edge_tracking_rounds`, which follow the weak edges
+/// through each tile with the border of the tile from the last round. The shader works in place
+/// though, so a border may already be from the current round. Both end with the same result unless
+/// the rounds run out, then the GPU may have promoted more weak edges.
+pub fn apply_edge_tracking(double_thresholding: &GrayF32Image, roi: Option<Roi>) -> GrayF32Image {
+    const TILE_SIZE: u32 = EDGE_TRACKING_TILE_SIZE;
+
+    let mut img = match roi {
+        Some(roi) => {
+            image::imageops::crop_imm(double_thresholding, roi.x, roi.y, roi.width, roi.height)
+                .to_image()
+        }
+        None => double_thresholding.clone(),
+    };
+    let (width, height) = img.dimensions();
+
+    for _ in 0..edge_tracking_rounds(width, height) {
+        let previous = img.clone();
+        let mut changed = false;
+
+        for tile_y in (0..height).step_by(TILE_SIZE as usize) {
+            for tile_x in (0..width).step_by(TILE_SIZE as usize) {
+                let x_range = tile_x..(tile_x + TILE_SIZE).min(width);
+                let y_range = tile_y..(tile_y + TILE_SIZE).min(height);
+                let is_in_tile = |x: u32, y: u32| x_range.contains(&x) && y_range.contains(&y);
+
+                // the edges of the tile and its border are followed through the tile
+                let mut stack = Vec::new();
+                for y in tile_y.saturating_sub(1)..(y_range.end + 1).min(height) {
+                    for x in tile_x.saturating_sub(1)..(x_range.end + 1).min(width) {
+                        let value = match is_in_tile(x, y) {
+                            true => img.get_pixel(x, y).0[0],
+                            false => previous.get_pixel(x, y).0[0],
+                        };
+                        if value == 1. {
+                            stack.push((x, y));
+                        }
+                    }
+                }
+
+                while let Some((x, y)) = stack.pop() {
+                    for dx in -1..=1 {
+                        for dy in -1..=1 {
+                            let (nx, ny) = (x as i64 + dx, y as i64 + dy);
+                            if nx < 0 || ny < 0 || !is_in_tile(nx as u32, ny as u32) {
+                                continue;
+                            }
+
+                            let neighbour = img.get_pixel_mut(nx as u32, ny as u32);
+                            if neighbour.0[0] != 1. && neighbour.0[0] != 0. {
+                                *neighbour = Luma([1.]);
+                                stack.push((nx as u32, ny as u32));
+                                changed = true;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        if !changed {
+            break;
+        }
+    }
+
+    img
+}
+
+/// Maps the values with the encoding, see `encoding.wgsl`.
+pub fn apply_output_encoding(img: &GrayF32Image, encoding: &OutputEncoding) -> GrayF32Image {
+    let absolute = |value: f32| {
+        if encoding.absolute {
+            value.abs()
+        } else {
+            value
+        }
+    };
+
+    let (min_value, max_value) = img
+        .pixels()
+        .map(|pixel| absolute(pixel.0[0]))
+        .filter(|value| !value.is_nan())
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), value| {
+            (min.min(value), max.max(value))
+        });
+
+    GrayF32Image::from_fn(img.width(), img.height(), |x, y| {
+        let mut value = absolute(img.get_pixel(x, y).0[0]);
+
+        if encoding.normalize {
+            value = if max_value > min_value {
+                (value - min_value) / (max_value - min_value)
+            } else {
+                0.
+            };
+        }
+
+        value = match encoding.transfer {
+            TransferFunction::Linear => value,
+            TransferFunction::Gamma => value.max(0.).powf(1. / 2.2),
+            TransferFunction::Srgb => {
+                let linear = value.max(0.);
+                if linear <= 0.0031308 {
+                    linear * 12.92
+                } else {
+                    1.055 * linear.powf(1. / 2.4) - 0.055
+                }
+            }
+        };
+
+        if encoding.invert {
+            value = 1. - value;
+        }
+
+        Luma([value])
+    })
+}
+
+/// Colours the gradients like `direction.wgsl`.
+pub fn apply_direction_visualization(
+    magnitudes: &GrayF32Image,
+    radians: &GrayF32Image,
+) -> RgbaImage {
+    let normalized = apply_output_encoding(
+        magnitudes,
+        &OutputEncoding {
+            transfer: TransferFunction::Gamma,
+            normalize: true,
+            ..Default::default()
+        },
+    );
+
+    RgbaImage::from_fn(magnitudes.width(), magnitudes.height(), |x, y| {
+        let [r, g, b] = direction_color(
+            radians.get_pixel(x, y).0[0],
+            normalized.get_pixel(x, y).0[0],
+        );
+        image::Rgba([r, g, b, u8::MAX])
+    })
+}
+
+/// Draws the edges over a `Rgba8Unorm` input like `overlay.wgsl`.
+pub fn apply_overlay(
+    input: &RgbaImage,
+    double_thresholding: &GrayF32Image,
+    edges: &GrayF32Image,
+    params: &OverlayParams,
+) -> RgbaImage {
+    let radius = params.dilation as i64;
+    let (width, height) = (edges.width() as i64, edges.height() as i64);
+
+    // `None` without an edge, `Some(true)` for a strong edge
+    let nearby_edge = |x: i64, y: i64| {
+        let mut edge = None;
+        for dy in -radius..=radius {
+            for dx in -radius..=radius {
+                let (nx, ny) = (x + dx, y + dy);
+                if dx * dx + dy * dy > radius * radius
+                    || nx < 0
+                    || ny < 0
+                    || nx >= width
+                    || ny >= height
+                    || edges.get_pixel(nx as u32, ny as u32).0[0] != 1.
+                {
+                    continue;
+                }
+
+                if double_thresholding.get_pixel(nx as u32, ny as u32).0[0] == 1. {
+                    return Some(true);
+                }
+                edge = Some(false);
+            }
+        }
+
+        edge
+    };
+
+    RgbaImage::from_fn(input.width(), input.height(), |x, y| {
+        let pixel = input.get_pixel(x, y).0;
+        let color = match nearby_edge(x as i64, y as i64) {
+            Some(true) => params.strong_color,
+            Some(false) => params.weak_color,
+            None => return image::Rgba([pixel[0], pixel[1], pixel[2], u8::MAX]),
+        };
+
+        let alpha = color[3] as f32 / u8::MAX as f32;
+        let blend = |channel: usize| {
+            let mixed = pixel[channel] as f32 * (1. - alpha) + color[channel] as f32 * alpha;
+            mixed.round() as u8
+        };
+        image::Rgba([blend(0), blend(1), blend(2), u8::MAX])
+    })
+}
+
+/// The deviation of the GPU from the CPU implementation in one stage.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StageError {
+    pub stage: &'static str,
+    pub max_abs_error: f32,
+    pub mean_abs_error: f32,
+}
+
+impl StageError {
+    fn new(stage: &'static str, gpu: &[f32], cpu: &GrayF32Image) -> Self {
+        let cpu = cpu.as_raw();
+        assert_eq!(gpu.len(), cpu.len(), "Stage '{stage}' has a different size");
+
+        let mut max_abs_error = 0f32;
+        let mut sum = 0f64;
+        for (gpu, cpu) in gpu.iter().zip(cpu) {
+            let error = (gpu - cpu).abs();
+            max_abs_error = max_abs_error.max(error);
+            sum += error as f64;
+        }
+
+        Self {
+            stage,
+            max_abs_error,
+            mean_abs_error: (sum / gpu.len().max(1) as f64) as f32,
+        }
+    }
+}
+
+/// Applies each stage on the GPU and on the CPU and returns how much they differ.
+///
+/// Both implementations get the GPU output of the previous stage as input, so each error only
+/// belongs to its own stage.
+pub fn compare_with_gpu(
+    renderer: &dyn Renderer,
+    img: &RgbaImage,
+    params: &CannyParams,
+) -> Vec<StageError> {
+    let read = |texture: &wgpu::Texture| {
+        GrayF32Image::from_raw(
+            texture.width(),
+            texture.height(),
+            read_texture(renderer, texture),
+        )
+        .unwrap()
+    };
+    let view =
+        |texture: &wgpu::Texture| texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+    let mut errors = Vec::new();
+
+    let input = crate::create_rgba_texture(renderer, img);
+    let gray_scale = read(
+        &crate::apply_grayscale(renderer, view(&input), None)
+            .expect("A Rgba8Unorm texture is a valid input"),
+    );
+    errors.push(StageError::new(
+        "gray scale",
+        &gray_scale,
+        &apply_grayscale(img, None),
+    ));
+
+    let gaussian_texture = crate::create_gray_texture(renderer, &gray_scale);
+    let gaussian = read(&crate::apply_gaussian_filter(
+        renderer,
+        view(&gaussian_texture),
+        params.sigma,
+        params.kernel_radius,
+        None,
+    ));
+    errors.push(StageError::new(
+        "gaussian",
+        &gaussian,
+        &apply_gaussian_filter(&gray_scale, params.sigma, params.kernel_radius, None),
+    ));
+
+    let gaussian_texture = crate::create_gray_texture(renderer, &gaussian);
+    let (horizontal, vertical) = crate::apply_sobel_operators(
+        renderer,
+        view(&gaussian_texture),
+        params.gradient_operator,
+        None,
+    );
+    let (horizontal, vertical) = (read(&horizontal), read(&vertical));
+    let (cpu_horizontal, cpu_vertical) =
+        apply_sobel_operators(&gaussian, params.gradient_operator, None);
+    errors.push(StageError::new("horizontal", &horizontal, &cpu_horizontal));
+    errors.push(StageError::new("vertical", &vertical, &cpu_vertical));
+
+    let (magnitudes, radians) = crate::apply_magnitude_and_angle(
+        renderer,
+        view(&crate::create_gray_texture(renderer, &vertical)),
+        view(&crate::create_gray_texture(renderer, &horizontal)),
+        None,
+    );
+    let (magnitudes, radians) = (read(&magnitudes), read(&radians));
+    let (cpu_magnitudes, cpu_radians) = apply_magnitude_and_angle(&vertical, &horizontal, None);
+    errors.push(StageError::new("magnitude", &magnitudes, &cpu_magnitudes));
+    errors.push(StageError::new("radians", &radians, &cpu_radians));
+
+    let non_maximum_suppression = read(&crate::apply_non_maximum_suppression(
+        renderer,
+        view(&crate::create_gray_texture(renderer, &magnitudes)),
+        view(&crate::create_gray_texture(renderer, &radians)),
+        params.nms_mode,
+        None,
+    ));
+    errors.push(StageError::new(
+        "non maximum suppression",
+        &non_maximum_suppression,
+        &apply_non_maximum_suppression(&magnitudes, &radians, params.nms_mode, None),
+    ));
+
+    let double_thresholding = read(&crate::apply_double_thresholding(
+        renderer,
+        view(&crate::create_gray_texture(
+            renderer,
+            &non_maximum_suppression,
+        )),
+        params.thresholds,
+        None,
+        &crate::EdgeMasks::default(),
+        None,
+    ));
+    errors.push(StageError::new(
+        "double thresholding",
+        &double_thresholding,
+        &apply_double_thresholding(
+            &non_maximum_suppression,
+            params.thresholds,
+            None,
+            &EdgeMasks::default(),
+            None,
+        ),
+    ));
+
+    let edge_tracking = read(&crate::apply_edge_tracking(
+        renderer,
+        view(&crate::create_gray_texture(renderer, &double_thresholding)),
+        None,
+    ));
+    errors.push(StageError::new(
+        "edge tracking",
+        &edge_tracking,
+        &apply_edge_tracking(&double_thresholding, None),
+    ));
+
+    errors
+}
@@ -0,0 +1,30 @@
+@group(0) @binding(0)
+var magnitudes: texture_2d<f32>;
+
+@group(0) @binding(1)
+var radians: texture_2d<f32>;
+
+@group(0) @binding(2)
+var output: texture_storage_2d<rgba8unorm, write>;
+
+const TAU: f32 = 6.283185307179586;
+
+fn hsv_to_rgb(hue: f32, saturation: f32, value: f32) -> vec3f {
+    let k = (vec3f(5., 3., 1.) + hue * 6.) % 6.;
+    return value - value * saturation * clamp(min(k, 4. - k), vec3f(0.), vec3f(1.));
+}
+
+@compute
+@workgroup_size(16, 16, 1)
+fn main(@builtin(global_invocation_id) gid: vec3u) {
+    let size = textureDimensions(magnitudes);
+    if (gid.x >= size.x || gid.y >= size.y) {
+        return;
+    }
+
+    // the magnitudes are normalized and gamma encoded by `apply_output_encoding`
+    let magnitude = textureLoad(magnitudes, gid.xy, 0).r;
+    let hue = fract(textureLoad(radians, gid.xy, 0).r / TAU);
+
+    textureStore(output, gid.xy, vec4f(hsv_to_rgb(hue, 1., magnitude), 1.));
+}
@@ -15,12 +15,35 @@ struct Thresholds {
 @group(0) @binding(3)
 var<uniform> thresholds: Thresholds;
 
+struct Roi {
+    origin: vec2u,
+    size: vec2u,
+};
+
+@group(0) @binding(4)
+var<uniform> roi: Roi;
+
+@group(0) @binding(5)
+var ignore_mask: texture_2d<f32>;
+
+@group(0) @binding(6)
+var seed_mask: texture_2d<f32>;
+
+struct Masks {
+    origin: vec2u,
+    use_ignore_mask: u32,
+    use_seed_mask: u32,
+};
+
+@group(0) @binding(7)
+var<uniform> masks: Masks;
+
 const u32_MAX: f32 = 4294967295.;
 
 @compute
 @workgroup_size(16, 16, 1)
 fn main(@builtin(global_invocation_id) gid: vec3u) {
-    let presence = textureLoad(input, gid.xy, 0).r;
+    let presence = textureLoad(input, gid.xy + roi.origin, 0).r;
 
     let upper = (f32(max_value) * thresholds.top) / u32_MAX;
     let lower = upper * thresholds.bottom;
@@ -34,5 +57,13 @@ fn main(@builtin(global_invocation_id) gid: vec3u) {
         value = 0.5;
     }
 
+    let mask_coord = gid.xy + roi.origin + masks.origin;
+    if (masks.use_seed_mask != 0u && textureLoad(seed_mask, mask_coord, 0).r > 0.) {
+        value = 1.;
+    }
+    if (masks.use_ignore_mask != 0u && textureLoad(ignore_mask, mask_coord, 0).r == 0.) {
+        value = 0.;
+    }
+
     textureStore(output, gid.xy, vec4f(value, 0., 0., 1.));
 }
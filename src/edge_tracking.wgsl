@@ -1,34 +1,103 @@
 @group(0) @binding(0)
 var img: texture_storage_2d<r32float, read_write>;
 
+// set to 1 if a weak edge was promoted, the next round is only dispatched if it is
+@group(0) @binding(1)
+var<storage, read_write> changed: atomic<u32>;
+
 const IS_EDGE: f32 = 1.0;
 const NOT_EDGE: f32 = 0.0;
 
+const TILE_SIZE: u32 = 16u;
+// the tile and a border of one pixel, which belongs to the neighbouring workgroups
+const BORDERED_SIZE: u32 = TILE_SIZE + 2u;
+
+const CLASS_NONE: u32 = 0u;
+const CLASS_WEAK: u32 = 1u;
+const CLASS_EDGE: u32 = 2u;
+
+var<workgroup> tile: array<atomic<u32>, BORDERED_SIZE * BORDERED_SIZE>;
+var<workgroup> tile_changed: atomic<u32>;
+var<workgroup> keep_going: u32;
+
 @compute
 @workgroup_size(16, 16, 1)
-fn main(@builtin(global_invocation_id) gid: vec3u) {
-    let curr_value = textureLoad(img, gid.xy).r;
-    if (curr_value == IS_EDGE) {
-        textureStore(img, gid.xy, vec4f(IS_EDGE, 0., 0., 1.));
-        return;
-    } else if (curr_value == NOT_EDGE) {
+fn main(
+    @builtin(global_invocation_id) gid: vec3u,
+    @builtin(workgroup_id) wid: vec3u,
+    @builtin(local_invocation_id) lid: vec3u,
+    @builtin(local_invocation_index) index: u32,
+) {
+    let tile_origin = vec2i(wid.xy * TILE_SIZE) - vec2i(1);
+    for (var i = index; i < BORDERED_SIZE * BORDERED_SIZE; i += TILE_SIZE * TILE_SIZE) {
+        let coord = tile_origin + vec2i(i32(i % BORDERED_SIZE), i32(i / BORDERED_SIZE));
+        atomicStore(&tile[i], class_of(coord));
+    }
+    workgroupBarrier();
+
+    // most tiles don't contain any weak edge
+    let center = (lid.y + 1u) * BORDERED_SIZE + lid.x + 1u;
+    if (atomicLoad(&tile[center]) == CLASS_WEAK) {
+        atomicStore(&tile_changed, 1u);
+    }
+    workgroupBarrier();
+    if (index == 0u) {
+        keep_going = atomicExchange(&tile_changed, 0u);
+    }
+    if (workgroupUniformLoad(&keep_going) == 0u) {
         return;
     }
 
-    for (var x = -1; x < 2; x++) {
-        for (var y = -1; y < 2; y++) {
-            let coord = vec2i(gid.xy) + vec2i(x, y);
+    // the weak edges are followed through the tile until nothing changes anymore, pixels outside of
+    // the texture are no edges, but still have to take part in the barriers
+    var promoted = false;
+    loop {
+        if (atomicLoad(&tile[center]) == CLASS_WEAK && has_edge_neighbour(center)) {
+            atomicStore(&tile[center], CLASS_EDGE);
+            atomicStore(&tile_changed, 1u);
+            promoted = true;
+        }
+        workgroupBarrier();
 
-            if is_in_texture(coord) {
-                let value = textureLoad(img, coord).r;
+        if (index == 0u) {
+            keep_going = atomicExchange(&tile_changed, 0u);
+        }
+        if (workgroupUniformLoad(&keep_going) == 0u) {
+            break;
+        }
+    }
+
+    if (promoted) {
+        textureStore(img, gid.xy, vec4f(IS_EDGE, 0., 0., 1.));
+        atomicStore(&changed, 1u);
+    }
+}
 
-                if (value == IS_EDGE) {
-                    textureStore(img, gid.xy, vec4f(IS_EDGE, 0., 0., 1.));
-                    return;
-                }
+fn has_edge_neighbour(center: u32) -> bool {
+    for (var y = 0u; y < 3u; y++) {
+        for (var x = 0u; x < 3u; x++) {
+            if (atomicLoad(&tile[center + y * BORDERED_SIZE + x - BORDERED_SIZE - 1u]) == CLASS_EDGE) {
+                return true;
             }
         }
     }
+
+    return false;
+}
+
+fn class_of(coord: vec2i) -> u32 {
+    if (!is_in_texture(coord)) {
+        return CLASS_NONE;
+    }
+
+    let value = textureLoad(img, coord).r;
+    if (value == IS_EDGE) {
+        return CLASS_EDGE;
+    } else if (value == NOT_EDGE) {
+        return CLASS_NONE;
+    }
+
+    return CLASS_WEAK;
 }
 
 fn is_in_texture(coord: vec2i) -> bool {
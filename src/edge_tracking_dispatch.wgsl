@@ -0,0 +1,20 @@
+// Runs between two rounds of the edge tracking: the indirect dispatch of the next round is emptied
+// if the last round didn't promote any weak edge.
+
+@group(0) @binding(0)
+var<storage, read_write> changed: u32;
+
+@group(0) @binding(1)
+var<storage, read_write> dispatch: array<u32, 3>;
+
+@compute
+@workgroup_size(1, 1, 1)
+fn main() {
+    if (changed == 0u) {
+        dispatch[0] = 0u;
+        dispatch[1] = 0u;
+        dispatch[2] = 0u;
+    }
+
+    changed = 0u;
+}
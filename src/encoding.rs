@@ -0,0 +1,141 @@
+use wgpu::util::DeviceExt;
+
+use crate::{OutputEncoding, Pipelines, Renderer, TransferFunction};
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct GpuOutputEncoding {
+    transfer: u32,
+    normalize: u32,
+    absolute: u32,
+    invert: u32,
+}
+
+impl From<&OutputEncoding> for GpuOutputEncoding {
+    fn from(encoding: &OutputEncoding) -> Self {
+        Self {
+            transfer: match encoding.transfer {
+                TransferFunction::Linear => 0,
+                TransferFunction::Gamma => 1,
+                TransferFunction::Srgb => 2,
+            },
+            normalize: encoding.normalize.into(),
+            absolute: encoding.absolute.into(),
+            invert: encoding.invert.into(),
+        }
+    }
+}
+
+/// Maps the values of a `R32Float` texture with the encoding, before it's read back and saved.
+pub fn apply_output_encoding(
+    renderer: &dyn Renderer,
+    input: wgpu::TextureView,
+    encoding: &OutputEncoding,
+) -> wgpu::Texture {
+    const WORKGROUP_SIZE: u32 = 16;
+
+    let device = renderer.device();
+
+    let texture = input.texture();
+
+    let out_texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Output encoding: Texture"),
+        size: texture.size(),
+        mip_level_count: texture.mip_level_count(),
+        sample_count: texture.sample_count(),
+        dimension: texture.dimension(),
+        format: wgpu::TextureFormat::R32Float,
+        usage: wgpu::TextureUsages::STORAGE_BINDING
+            | wgpu::TextureUsages::COPY_SRC
+            | wgpu::TextureUsages::TEXTURE_BINDING,
+        view_formats: &[],
+    });
+
+    let encoding_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Output encoding: Encoding buffer"),
+        contents: bytemuck::bytes_of(&GpuOutputEncoding::from(encoding)),
+        usage: wgpu::BufferUsages::UNIFORM,
+    });
+
+    // the min and max start at the opposite ends, see `ordered_bits` in `min_max.wgsl`
+    let min_max_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Output encoding: Min max buffer"),
+        contents: bytemuck::cast_slice(&[u32::MAX, 0]),
+        usage: wgpu::BufferUsages::STORAGE,
+    });
+
+    let min_max_pipeline = Pipelines::min_max(renderer);
+    let encoding_pipeline = Pipelines::output_encoding(renderer);
+
+    let min_max_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Output encoding: Min max bind group"),
+        layout: &min_max_pipeline.get_bind_group_layout(0),
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&input),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: min_max_buffer.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: encoding_buffer.as_entire_binding(),
+            },
+        ],
+    });
+
+    let encoding_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Output encoding: Bind group"),
+        layout: &encoding_pipeline.get_bind_group_layout(0),
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&input),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::TextureView(
+                    &out_texture.create_view(&wgpu::TextureViewDescriptor::default()),
+                ),
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: min_max_buffer.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 3,
+                resource: encoding_buffer.as_entire_binding(),
+            },
+        ],
+    });
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Output encoding: Command encoder"),
+    });
+
+    {
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Output encoding: Compute pass"),
+            timestamp_writes: crate::profile::timestamp_writes(renderer, "output encoding"),
+        });
+
+        let workgroups_x = texture.width().div_ceil(WORKGROUP_SIZE);
+        let workgroups_y = texture.height().div_ceil(WORKGROUP_SIZE);
+
+        if encoding.normalize {
+            pass.set_bind_group(0, &min_max_bind_group, &[]);
+            pass.set_pipeline(&min_max_pipeline);
+            pass.dispatch_workgroups(workgroups_x, workgroups_y, 1);
+        }
+
+        pass.set_bind_group(0, &encoding_bind_group, &[]);
+        pass.set_pipeline(&encoding_pipeline);
+        pass.dispatch_workgroups(workgroups_x, workgroups_y, 1);
+    }
+
+    crate::profile::submit(renderer, "output encoding", encoder);
+
+    out_texture
+}
@@ -0,0 +1,66 @@
+@group(0) @binding(0)
+var input: texture_2d<f32>;
+
+@group(0) @binding(1)
+var output: texture_storage_2d<r32float, write>;
+
+// written by `min_max.wgsl`
+@group(0) @binding(2)
+var<storage, read> min_max: array<u32, 2>;
+
+struct Encoding {
+    // 0: linear, 1: gamma 2.2, 2: sRGB
+    transfer: u32,
+    normalize: u32,
+    absolute: u32,
+    invert: u32,
+};
+
+@group(0) @binding(3)
+var<uniform> encoding: Encoding;
+
+fn from_ordered_bits(bits: u32) -> f32 {
+    return bitcast<f32>(select(~bits, bits & 0x7fffffffu, (bits & 0x80000000u) != 0u));
+}
+
+fn encode_srgb(linear: f32) -> f32 {
+    if linear <= 0.0031308 {
+        return linear * 12.92;
+    }
+    return 1.055 * pow(linear, 1. / 2.4) - 0.055;
+}
+
+@compute
+@workgroup_size(16, 16, 1)
+fn main(@builtin(global_invocation_id) gid: vec3u) {
+    let size = textureDimensions(input);
+    if (gid.x >= size.x || gid.y >= size.y) {
+        return;
+    }
+
+    var value = textureLoad(input, gid.xy, 0).r;
+
+    if encoding.absolute != 0u {
+        value = abs(value);
+    }
+
+    if encoding.normalize != 0u {
+        let min_value = from_ordered_bits(min_max[0]);
+        let max_value = from_ordered_bits(min_max[1]);
+        // an image with a single value becomes black
+        value = select(0., (value - min_value) / (max_value - min_value), max_value > min_value);
+    }
+
+    // negative values have no gamma encoding
+    if encoding.transfer == 1u {
+        value = pow(max(value, 0.), 1. / 2.2);
+    } else if encoding.transfer == 2u {
+        value = encode_srgb(max(value, 0.));
+    }
+
+    if encoding.invert != 0u {
+        value = 1. - value;
+    }
+
+    textureStore(output, gid.xy, vec4f(value, 0., 0., 1.));
+}
@@ -0,0 +1,255 @@
+use std::sync::OnceLock;
+
+use image::{DynamicImage, GrayImage};
+use pollster::FutureExt;
+
+use crate::{
+    CannyParams, DevicePoller, EdgeMasks, Pipelines, Renderer, Roi, apply_canny, apply_canny_tiled,
+    create_image_texture, forbid_blocking, read_texture, read_texture_async,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CannyError {
+    /// wgpu didn't find an adapter, not even a software one.
+    NoAdapter(String),
+    /// The adapter couldn't create a device.
+    RequestDevice(String),
+    /// The image has a width or height of zero.
+    EmptyImage,
+    /// wgpu reported a validation or out of memory error while applying the stages.
+    Gpu(String),
+    /// The input texture has a format which isn't in `INPUT_FORMATS`.
+    UnsupportedFormat(wgpu::TextureFormat),
+    /// The input texture lacks a usage which the stages need.
+    MissingUsage(wgpu::TextureUsages),
+    /// The parameters of a stage contradict each other.
+    InvalidParams(String),
+    /// The ROI is empty or doesn't lie inside of the `width`x`height` texture.
+    InvalidRoi { roi: Roi, width: u32, height: u32 },
+}
+
+impl std::fmt::Display for CannyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NoAdapter(err) => write!(f, "Get adapter: {err}"),
+            Self::RequestDevice(err) => write!(f, "Get device: {err}"),
+            Self::EmptyImage => write!(f, "The image is empty"),
+            Self::Gpu(err) => write!(f, "GPU error: {err}"),
+            Self::UnsupportedFormat(format) => {
+                write!(
+                    f,
+                    "Input textures with the format {format:?} aren't supported"
+                )
+            }
+            Self::MissingUsage(usage) => write!(f, "The input texture needs the usage {usage:?}"),
+            Self::InvalidParams(err) => write!(f, "Invalid parameters: {err}"),
+            Self::InvalidRoi { roi, width, height } => write!(
+                f,
+                "The ROI {roi:?} is empty or doesn't lie inside of the {width}x{height} texture"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CannyError {}
+
+/// A renderer with its own device and pipeline cache, for users who don't manage a wgpu device
+/// themselves.
+///
+/// Its device is polled by a `DevicePoller`, so it can be used with the async functions.
+#[derive(Debug)]
+pub struct GpuRenderer {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    pipelines: Pipelines,
+    _poller: DevicePoller,
+}
+
+impl GpuRenderer {
+    /// Uses the default adapter and falls back to a software adapter if there's none.
+    pub fn new() -> Result<Self, CannyError> {
+        Self::new_async().block_on()
+    }
+
+    pub async fn new_async() -> Result<Self, CannyError> {
+        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::from_env_or_default());
+
+        let adapter = match instance
+            .request_adapter(&wgpu::RequestAdapterOptions::default())
+            .await
+        {
+            Ok(adapter) => adapter,
+            Err(_) => instance
+                .request_adapter(&wgpu::RequestAdapterOptions {
+                    force_fallback_adapter: true,
+                    ..Default::default()
+                })
+                .await
+                .map_err(|err| CannyError::NoAdapter(err.to_string()))?,
+        };
+
+        let (device, queue) = adapter
+            .request_device(&wgpu::DeviceDescriptor {
+                // bigger images fit into a single texture
+                required_limits: adapter.limits(),
+                ..Default::default()
+            })
+            .await
+            .map_err(|err| CannyError::RequestDevice(err.to_string()))?;
+
+        Ok(Self {
+            _poller: DevicePoller::new(device.clone()),
+            device,
+            queue,
+            pipelines: Pipelines::default(),
+        })
+    }
+
+    /// The renderer which `canny_image` uses. It's created by the first call.
+    pub fn shared() -> Result<&'static Self, CannyError> {
+        static RENDERER: OnceLock<Result<GpuRenderer, CannyError>> = OnceLock::new();
+
+        RENDERER
+            .get_or_init(Self::new)
+            .as_ref()
+            .map_err(Clone::clone)
+    }
+}
+
+impl Renderer for GpuRenderer {
+    fn device(&self) -> &wgpu::Device {
+        &self.device
+    }
+
+    fn queue(&self) -> &wgpu::Queue {
+        &self.queue
+    }
+
+    fn pipelines(&self) -> Option<&Pipelines> {
+        Some(&self.pipelines)
+    }
+}
+
+/// Applies the canny edge detection to `img` on the GPU. Edges are `255`, everything else is `0`.
+///
+/// Uses `GpuRenderer::shared`, so the device and the compiled pipelines are reused by later calls.
+pub fn canny_image(img: &DynamicImage, params: &CannyParams) -> Result<GrayImage, CannyError> {
+    canny_image_with_renderer(GpuRenderer::shared()?, img, params)
+}
+
+/// Same as `canny_image`, but waits for the GPU without blocking the thread.
+///
+/// The first call creates the device of `GpuRenderer::shared`, which blocks.
+pub async fn canny_image_async(
+    img: &DynamicImage,
+    params: &CannyParams,
+) -> Result<GrayImage, CannyError> {
+    canny_image_with_renderer_async(GpuRenderer::shared()?, img, params).await
+}
+
+/// Same as `canny_image`, but with the given renderer.
+///
+/// Images which are too big for a single texture are processed in tiles, see `apply_canny_tiled`.
+pub fn canny_image_with_renderer(
+    renderer: &dyn Renderer,
+    img: &DynamicImage,
+    params: &CannyParams,
+) -> Result<GrayImage, CannyError> {
+    check_size(img)?;
+    if needs_tiles(renderer, img) {
+        return apply_canny_tiled(
+            renderer,
+            img,
+            params,
+            renderer.device().limits().max_texture_dimension_2d,
+        );
+    }
+
+    let edges = apply_in_error_scopes(renderer, img, params);
+    pop_error_scopes(renderer.device()).block_on()?;
+
+    Ok(binarize(img, read_texture(renderer, &edges?)))
+}
+
+/// Same as `canny_image_with_renderer`, but waits for the GPU without blocking the thread.
+///
+/// The device of the renderer has to be polled by someone else, for example by a `DevicePoller`.
+/// Images which are too big for a single texture are processed in tiles, which blocks, and so does
+/// a profiler which measures the stages with the wall clock.
+pub async fn canny_image_with_renderer_async(
+    renderer: &(dyn Renderer + Sync),
+    img: &DynamicImage,
+    params: &CannyParams,
+) -> Result<GrayImage, CannyError> {
+    check_size(img)?;
+    if needs_tiles(renderer, img) {
+        return apply_canny_tiled(
+            renderer,
+            img,
+            params,
+            renderer.device().limits().max_texture_dimension_2d,
+        );
+    }
+
+    let edges = forbid_blocking(|| apply_in_error_scopes(renderer, img, params));
+    pop_error_scopes(renderer.device()).await?;
+
+    Ok(binarize(img, read_texture_async(renderer, &edges?).await))
+}
+
+fn check_size(img: &DynamicImage) -> Result<(), CannyError> {
+    if img.width() == 0 || img.height() == 0 {
+        return Err(CannyError::EmptyImage);
+    }
+
+    Ok(())
+}
+
+fn needs_tiles(renderer: &dyn Renderer, img: &DynamicImage) -> bool {
+    let max_texture_dimension = renderer.device().limits().max_texture_dimension_2d;
+    img.width() > max_texture_dimension || img.height() > max_texture_dimension
+}
+
+/// Applies the stages inside of error scopes, which have to be popped by `pop_error_scopes`.
+fn apply_in_error_scopes(
+    renderer: &dyn Renderer,
+    img: &DynamicImage,
+    params: &CannyParams,
+) -> Result<wgpu::Texture, CannyError> {
+    let device = renderer.device();
+    device.push_error_scope(wgpu::ErrorFilter::OutOfMemory);
+    device.push_error_scope(wgpu::ErrorFilter::Validation);
+
+    let input_texture = create_image_texture(renderer, img);
+    apply_canny(
+        renderer,
+        input_texture.create_view(&wgpu::TextureViewDescriptor::default()),
+        params,
+        None,
+        &EdgeMasks::default(),
+    )
+}
+
+async fn pop_error_scopes(device: &wgpu::Device) -> Result<(), CannyError> {
+    // the scopes belong to the thread, so both are popped before the task can move to another one
+    let validation_error = device.pop_error_scope();
+    let out_of_memory_error = device.pop_error_scope();
+    let (validation_error, out_of_memory_error) =
+        (validation_error.await, out_of_memory_error.await);
+
+    match validation_error.or(out_of_memory_error) {
+        Some(error) => Err(CannyError::Gpu(error.to_string())),
+        None => Ok(()),
+    }
+}
+
+/// Turns the values of the final edge texture into `255` for edges and `0` for everything else.
+fn binarize(img: &DynamicImage, values: Vec<f32>) -> GrayImage {
+    // weak edges which weren't connected to an edge are left over with a value of 0.5
+    let values = values
+        .into_iter()
+        .map(|value| if value >= 1. { u8::MAX } else { 0 })
+        .collect();
+
+    GrayImage::from_raw(img.width(), img.height(), values).unwrap()
+}
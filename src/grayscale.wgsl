@@ -4,12 +4,44 @@ var input: texture_2d<f32>;
 @group(0) @binding(1)
 var output: texture_storage_2d<r32float, write>;
 
+struct Roi {
+    origin: vec2u,
+    size: vec2u,
+};
+
+@group(0) @binding(2)
+var<uniform> roi: Roi;
+
+struct InputFormat {
+    // the input has only a red channel, which is the gray value
+    single_channel: u32,
+    // the input is decoded from sRGB when it's loaded and has to be encoded again
+    srgb: u32,
+};
+
+@group(0) @binding(3)
+var<uniform> input_format: InputFormat;
+
+fn encode_srgb(linear: vec3f) -> vec3f {
+    let low = linear * 12.92;
+    let high = 1.055 * pow(linear, vec3f(1. / 2.4)) - 0.055;
+    return select(high, low, linear <= vec3f(0.0031308));
+}
+
 @compute
 @workgroup_size(16, 16, 1)
 fn main(@builtin(global_invocation_id) gid: vec3u) {
-    let pixel = textureLoad(input, gid.xy, 0);
+    var pixel = textureLoad(input, gid.xy + roi.origin, 0);
+
+    if input_format.single_channel != 0 {
+        textureStore(output, gid.xy, vec4f(pixel.r, 0., 0., 1.));
+        return;
+    }
+
+    if input_format.srgb != 0 {
+        pixel = vec4f(encode_srgb(pixel.rgb), pixel.a);
+    }
 
     let luminance = 0.3 * pixel.r + 0.59 * pixel.g + 0.11 * pixel.b;
     textureStore(output, gid.xy, vec4f(luminance, 0., 0., 1.));
 }
-
@@ -0,0 +1,603 @@
+use wgpu::util::DeviceExt;
+
+use crate::{CannyError, Pipelines, Renderer, read_buffer, read_texture};
+
+const IS_EDGE: f32 = 1.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HoughLinesParams {
+    /// Distance resolution of the accumulator in pixels.
+    pub rho_resolution: f32,
+    /// Angle resolution of the accumulator in radians.
+    pub theta_resolution: f32,
+    /// Minimum amount of votes a line needs to be returned.
+    pub threshold: u32,
+}
+
+impl Default for HoughLinesParams {
+    fn default() -> Self {
+        Self {
+            rho_resolution: 1.,
+            theta_resolution: std::f32::consts::PI / 180.,
+            threshold: 50,
+        }
+    }
+}
+
+impl HoughLinesParams {
+    pub fn validate(&self) -> Result<(), CannyError> {
+        for (name, resolution) in [
+            ("rho", self.rho_resolution),
+            ("theta", self.theta_resolution),
+        ] {
+            if !(resolution.is_finite() && resolution > 0.) {
+                return Err(CannyError::InvalidParams(format!(
+                    "The {name} resolution ({resolution}) has to be a positive number"
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HoughLineSegmentsParams {
+    pub lines: HoughLinesParams,
+    /// Segments which are shorter than this (in pixels) are dropped.
+    pub min_line_length: f32,
+    /// Maximum amount of missing edge pixels between two points of the same segment.
+    pub max_line_gap: u32,
+}
+
+impl Default for HoughLineSegmentsParams {
+    fn default() -> Self {
+        Self {
+            lines: HoughLinesParams::default(),
+            min_line_length: 30.,
+            max_line_gap: 5,
+        }
+    }
+}
+
+/// An infinite line in its normal form: `x * cos(theta) + y * sin(theta) = rho`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HoughLine {
+    pub rho: f32,
+    pub theta: f32,
+    pub votes: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LineSegment {
+    pub start: [u32; 2],
+    pub end: [u32; 2],
+}
+
+impl LineSegment {
+    pub fn length(&self) -> f32 {
+        let dx = self.end[0] as f32 - self.start[0] as f32;
+        let dy = self.end[1] as f32 - self.start[1] as f32;
+
+        (dx * dx + dy * dy).sqrt()
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct GpuHoughLinesParams {
+    rho_resolution: f32,
+    theta_resolution: f32,
+    num_rho: u32,
+    num_theta: u32,
+}
+
+/// Returns the lines of the given edge texture (output of `apply_edge_tracking`), sorted by their votes.
+pub fn apply_hough_lines(
+    renderer: &dyn Renderer,
+    edges: wgpu::TextureView,
+    params: &HoughLinesParams,
+) -> Result<Vec<HoughLine>, CannyError> {
+    const WORKGROUP_SIZE: u32 = 16;
+
+    params.validate()?;
+
+    let device = renderer.device();
+
+    let texture = edges.texture();
+
+    // the amount of bins is computed in f64/u64, tiny resolutions would overflow an u32
+    let max_rho = (texture.width() as f64).hypot(texture.height() as f64);
+    let num_rho = (2. * max_rho / params.rho_resolution as f64).ceil() as u64 + 1;
+    let num_theta = ((std::f64::consts::PI / params.theta_resolution as f64).round() as u64).max(1);
+    let size = num_rho
+        .saturating_mul(num_theta)
+        .saturating_mul(std::mem::size_of::<u32>() as u64);
+    let max_size = device.limits().max_storage_buffer_binding_size as u64;
+    if size > max_size {
+        return Err(CannyError::InvalidParams(format!(
+            "The accumulator of {num_rho}x{num_theta} bins is bigger than {max_size} bytes, \
+             the rho or theta resolution is too fine"
+        )));
+    }
+
+    let gpu_params = GpuHoughLinesParams {
+        rho_resolution: params.rho_resolution,
+        theta_resolution: params.theta_resolution,
+        num_rho: num_rho as u32,
+        num_theta: num_theta as u32,
+    };
+
+    let accumulator = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Hough lines: Accumulator buffer"),
+        size,
+        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+        mapped_at_creation: false,
+    });
+
+    let params_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Hough lines: Params buffer"),
+        contents: bytemuck::bytes_of(&gpu_params),
+        usage: wgpu::BufferUsages::UNIFORM,
+    });
+
+    let pipeline = Pipelines::hough_lines(renderer);
+
+    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Hough lines: Bind group 0"),
+        layout: &pipeline.get_bind_group_layout(0),
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&edges),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: accumulator.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: params_buffer.as_entire_binding(),
+            },
+        ],
+    });
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Hough lines: Command encoder"),
+    });
+
+    {
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Hough lines: Compute pass"),
+            timestamp_writes: crate::profile::timestamp_writes(renderer, "hough lines"),
+        });
+
+        pass.set_bind_group(0, &bind_group, &[]);
+        pass.set_pipeline(&pipeline);
+        pass.dispatch_workgroups(
+            texture.width().div_ceil(WORKGROUP_SIZE),
+            texture.height().div_ceil(WORKGROUP_SIZE),
+            1,
+        );
+    }
+
+    crate::profile::submit(renderer, "hough lines", encoder);
+
+    let bytes = read_buffer(renderer, &accumulator);
+    let votes: &[u32] = bytemuck::cast_slice(&bytes);
+
+    Ok(find_line_peaks(votes, &gpu_params, params.threshold))
+}
+
+/// Returns the finite line segments of the given edge texture (output of `apply_edge_tracking`),
+/// similar to OpenCV's `HoughLinesP`.
+///
+/// Each line of `apply_hough_lines` is walked through the edge map and split into segments
+/// wherever more than `max_line_gap` edge pixels are missing. Edge pixels of an accepted segment
+/// are removed so they can't contribute to another segment.
+pub fn apply_hough_line_segments(
+    renderer: &dyn Renderer,
+    edges: wgpu::TextureView,
+    params: &HoughLineSegmentsParams,
+) -> Result<Vec<LineSegment>, CannyError> {
+    let texture = edges.texture().clone();
+    let lines = apply_hough_lines(renderer, edges, &params.lines)?;
+
+    let width = texture.width() as i64;
+    let height = texture.height() as i64;
+    let mut edge_map: Vec<bool> = read_texture(renderer, &texture)
+        .into_iter()
+        .map(|value| value == IS_EDGE)
+        .collect();
+
+    let mut segments = Vec::new();
+    for line in lines {
+        let (sin, cos) = line.theta.sin_cos();
+
+        // walk along the dominant axis so that every pixel column (or row) is visited once
+        let walks_along_x = sin.abs() > cos.abs();
+        let (steps, perpendicular_offsets) = if walks_along_x {
+            (width, [(0, 0), (0, -1), (0, 1)])
+        } else {
+            (height, [(0, 0), (-1, 0), (1, 0)])
+        };
+
+        let mut current: Option<([i64; 2], [i64; 2])> = None;
+        let mut gap = 0;
+        let mut hits: Vec<usize> = Vec::new();
+
+        let mut flush = |current: &mut Option<([i64; 2], [i64; 2])>,
+                         hits: &mut Vec<usize>,
+                         edge_map: &mut Vec<bool>| {
+            if let Some((start, end)) = current.take() {
+                let segment = LineSegment {
+                    start: [start[0] as u32, start[1] as u32],
+                    end: [end[0] as u32, end[1] as u32],
+                };
+
+                if segment.length() >= params.min_line_length {
+                    for &idx in hits.iter() {
+                        edge_map[idx] = false;
+                    }
+                    segments.push(segment);
+                }
+            }
+            hits.clear();
+        };
+
+        for step in 0..steps {
+            let (x, y) = if walks_along_x {
+                (step, ((line.rho - step as f32 * cos) / sin).round() as i64)
+            } else {
+                (((line.rho - step as f32 * sin) / cos).round() as i64, step)
+            };
+
+            let hit = perpendicular_offsets.iter().find_map(|(dx, dy)| {
+                let (px, py) = (x + dx, y + dy);
+                let is_inside = px >= 0 && px < width && py >= 0 && py < height;
+                let idx = (py * width + px) as usize;
+
+                (is_inside && edge_map[idx]).then_some((idx, [px, py]))
+            });
+
+            match hit {
+                Some((idx, point)) => {
+                    gap = 0;
+                    hits.push(idx);
+                    match current.as_mut() {
+                        Some((_, end)) => *end = point,
+                        None => current = Some((point, point)),
+                    }
+                }
+                None if current.is_some() => {
+                    gap += 1;
+                    if gap > params.max_line_gap {
+                        flush(&mut current, &mut hits, &mut edge_map);
+                    }
+                }
+                None => {}
+            }
+        }
+
+        flush(&mut current, &mut hits, &mut edge_map);
+    }
+
+    Ok(segments)
+}
+
+fn find_line_peaks(votes: &[u32], params: &GpuHoughLinesParams, threshold: u32) -> Vec<HoughLine> {
+    let num_rho = params.num_rho as usize;
+    let num_theta = params.num_theta as usize;
+    let max_rho = (num_rho - 1) as f32 * params.rho_resolution * 0.5;
+
+    let mut lines = Vec::new();
+    for theta_idx in 0..num_theta {
+        for rho_idx in 0..num_rho {
+            let value = votes[theta_idx * num_rho + rho_idx];
+            if value < threshold {
+                continue;
+            }
+
+            // only keep local maxima of the 3x3 neighbourhood
+            let is_peak = (-1isize..=1)
+                .flat_map(|dt| (-1isize..=1).map(move |dr| (dt, dr)))
+                .filter(|&offset| offset != (0, 0))
+                .all(|(dt, dr)| {
+                    let mut t = theta_idx as isize + dt;
+                    let mut r = rho_idx as isize + dr;
+                    // `(rho, theta)` and `(-rho, theta + pi)` are the same line, so the neighbours
+                    // of the first and the last angle wrap around with a mirrored rho
+                    if t < 0 || t >= num_theta as isize {
+                        t = t.rem_euclid(num_theta as isize);
+                        r = num_rho as isize - 1 - r;
+                    }
+                    if r < 0 || r >= num_rho as isize {
+                        return true;
+                    }
+
+                    let neighbour = votes[t as usize * num_rho + r as usize];
+                    // break ties by preferring the first bin
+                    neighbour < value || (neighbour == value && (dt, dr) > (0, 0))
+                });
+
+            if is_peak {
+                lines.push(HoughLine {
+                    rho: rho_idx as f32 * params.rho_resolution - max_rho,
+                    theta: theta_idx as f32 * params.theta_resolution,
+                    votes: value,
+                });
+            }
+        }
+    }
+
+    lines.sort_by_key(|line| std::cmp::Reverse(line.votes));
+    lines
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HoughCirclesParams {
+    /// Smallest radius (in pixels) to look for.
+    pub min_radius: u32,
+    /// Biggest radius (in pixels) to look for.
+    pub max_radius: u32,
+    /// Pixels with a smaller gradient magnitude don't vote.
+    pub min_magnitude: f32,
+    /// Minimum amount of votes a centre needs to be returned.
+    pub threshold: u32,
+    /// Minimum distance (in pixels) between the centres of two circles.
+    pub min_distance: f32,
+}
+
+impl Default for HoughCirclesParams {
+    fn default() -> Self {
+        Self {
+            min_radius: 5,
+            max_radius: 50,
+            min_magnitude: 0.3,
+            threshold: 30,
+            min_distance: 10.,
+        }
+    }
+}
+
+impl HoughCirclesParams {
+    pub fn validate(&self) -> Result<(), CannyError> {
+        if self.min_radius > self.max_radius {
+            return Err(CannyError::InvalidParams(format!(
+                "The min radius ({}) is bigger than the max radius ({})",
+                self.min_radius, self.max_radius
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HoughCircle {
+    pub centre: [u32; 2],
+    pub radius: u32,
+    pub votes: u32,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct GpuHoughCirclesParams {
+    min_radius: u32,
+    max_radius: u32,
+    min_magnitude: f32,
+    _padding: u32,
+}
+
+/// Returns the circles which are found by casting votes along the gradient direction, sorted by their votes.
+///
+/// `magnitudes` and `radians` are the outputs of `apply_magnitude_and_angle`.
+/// The radius of each centre is the one which is supported by the most pixels with a high enough
+/// magnitude and a gradient which points at (or away from) the centre.
+pub fn apply_hough_circles(
+    renderer: &dyn Renderer,
+    magnitudes: wgpu::TextureView,
+    radians: wgpu::TextureView,
+    params: &HoughCirclesParams,
+) -> Result<Vec<HoughCircle>, CannyError> {
+    const WORKGROUP_SIZE: u32 = 16;
+
+    params.validate()?;
+
+    let device = renderer.device();
+
+    let m_texture = magnitudes.texture();
+    let width = m_texture.width();
+    let height = m_texture.height();
+
+    let accumulator = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Hough circles: Accumulator buffer"),
+        size: (width * height) as wgpu::BufferAddress
+            * std::mem::size_of::<u32>() as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+        mapped_at_creation: false,
+    });
+
+    let params_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Hough circles: Params buffer"),
+        contents: bytemuck::bytes_of(&GpuHoughCirclesParams {
+            min_radius: params.min_radius,
+            max_radius: params.max_radius,
+            min_magnitude: params.min_magnitude,
+            _padding: 0,
+        }),
+        usage: wgpu::BufferUsages::UNIFORM,
+    });
+
+    let pipeline = Pipelines::hough_circles(renderer);
+
+    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Hough circles: Bind group 0"),
+        layout: &pipeline.get_bind_group_layout(0),
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&magnitudes),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::TextureView(&radians),
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: accumulator.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 3,
+                resource: params_buffer.as_entire_binding(),
+            },
+        ],
+    });
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Hough circles: Command encoder"),
+    });
+
+    {
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Hough circles: Compute pass"),
+            timestamp_writes: crate::profile::timestamp_writes(renderer, "hough circles"),
+        });
+
+        pass.set_bind_group(0, &bind_group, &[]);
+        pass.set_pipeline(&pipeline);
+        pass.dispatch_workgroups(
+            width.div_ceil(WORKGROUP_SIZE),
+            height.div_ceil(WORKGROUP_SIZE),
+            1,
+        );
+    }
+
+    crate::profile::submit(renderer, "hough circles", encoder);
+
+    let bytes = read_buffer(renderer, &accumulator);
+    let votes: &[u32] = bytemuck::cast_slice(&bytes);
+
+    let centres = find_circle_centres(votes, width, height, params);
+    if centres.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let edge_pixels: Vec<EdgePixel> = read_texture(renderer, m_texture)
+        .into_iter()
+        .zip(read_texture(renderer, radians.texture()))
+        .enumerate()
+        .filter(|(_, (magnitude, _))| *magnitude >= params.min_magnitude)
+        .map(|(idx, (_, radian))| EdgePixel {
+            position: [(idx as u32 % width) as f32, (idx as u32 / width) as f32],
+            direction: [radian.cos(), radian.sin()],
+        })
+        .collect();
+
+    Ok(centres
+        .into_iter()
+        .map(|(centre, votes)| HoughCircle {
+            centre,
+            radius: estimate_radius(centre, &edge_pixels, params),
+            votes,
+        })
+        .collect())
+}
+
+struct EdgePixel {
+    position: [f32; 2],
+    /// Unit vector along the gradient.
+    direction: [f32; 2],
+}
+
+fn find_circle_centres(
+    votes: &[u32],
+    width: u32,
+    height: u32,
+    params: &HoughCirclesParams,
+) -> Vec<([u32; 2], u32)> {
+    let mut candidates = Vec::new();
+    for y in 0..height as i64 {
+        for x in 0..width as i64 {
+            let value = votes[(y * width as i64 + x) as usize];
+            if value < params.threshold {
+                continue;
+            }
+
+            let is_peak = (-1i64..=1)
+                .flat_map(|dy| (-1i64..=1).map(move |dx| (dx, dy)))
+                .filter(|&offset| offset != (0, 0))
+                .all(|(dx, dy)| {
+                    let (nx, ny) = (x + dx, y + dy);
+                    if nx < 0 || nx >= width as i64 || ny < 0 || ny >= height as i64 {
+                        return true;
+                    }
+
+                    let neighbour = votes[(ny * width as i64 + nx) as usize];
+                    // break ties by preferring the first pixel
+                    neighbour < value || (neighbour == value && (dy, dx) > (0, 0))
+                });
+
+            if is_peak {
+                candidates.push(([x as u32, y as u32], value));
+            }
+        }
+    }
+
+    candidates.sort_by_key(|(_, votes)| std::cmp::Reverse(*votes));
+
+    // strongest centres win against weaker centres which are too close to them
+    let mut centres: Vec<([u32; 2], u32)> = Vec::new();
+    for (centre, votes) in candidates {
+        let is_far_enough = centres.iter().all(|(other, _)| {
+            let dx = centre[0] as f32 - other[0] as f32;
+            let dy = centre[1] as f32 - other[1] as f32;
+
+            dx.hypot(dy) >= params.min_distance
+        });
+
+        if is_far_enough {
+            centres.push((centre, votes));
+        }
+    }
+
+    centres
+}
+
+fn estimate_radius(
+    centre: [u32; 2],
+    edge_pixels: &[EdgePixel],
+    params: &HoughCirclesParams,
+) -> u32 {
+    /// Cosine of the biggest angle between the gradient and the direction to the centre (about 25°).
+    const MIN_ALIGNMENT: f32 = 0.9;
+
+    let mut histogram = vec![0u32; (params.max_radius - params.min_radius + 1) as usize];
+
+    for pixel in edge_pixels {
+        let dx = centre[0] as f32 - pixel.position[0];
+        let dy = centre[1] as f32 - pixel.position[1];
+        let distance = dx.hypot(dy);
+
+        // pixels of other shapes or circles don't point at the centre, the sign depends on whether
+        // the circle is brighter or darker than the background
+        let alignment = (dx * pixel.direction[0] + dy * pixel.direction[1]) / distance;
+        if distance > 0. && alignment.abs() < MIN_ALIGNMENT {
+            continue;
+        }
+
+        let distance = distance.round() as u32;
+        if (params.min_radius..=params.max_radius).contains(&distance) {
+            histogram[(distance - params.min_radius) as usize] += 1;
+        }
+    }
+
+    let best_idx = histogram
+        .iter()
+        .enumerate()
+        .max_by_key(|(idx, count)| (**count, std::cmp::Reverse(*idx)))
+        .map(|(idx, _)| idx)
+        .unwrap_or(0);
+
+    params.min_radius + best_idx as u32
+}
@@ -0,0 +1,47 @@
+@group(0) @binding(0)
+var magnitudes: texture_2d<f32>;
+
+@group(0) @binding(1)
+var radians: texture_2d<f32>;
+
+@group(0) @binding(2)
+var<storage, read_write> accumulator: array<atomic<u32>>;
+
+struct Params {
+    min_radius: u32,
+    max_radius: u32,
+    min_magnitude: f32,
+};
+
+@group(0) @binding(3)
+var<uniform> params: Params;
+
+@compute
+@workgroup_size(16, 16, 1)
+fn main(@builtin(global_invocation_id) gid: vec3u) {
+    let size = vec2i(textureDimensions(magnitudes));
+    if (i32(gid.x) >= size.x || i32(gid.y) >= size.y) {
+        return;
+    }
+
+    let magnitude = textureLoad(magnitudes, gid.xy, 0).r;
+    if (magnitude < params.min_magnitude) {
+        return;
+    }
+
+    let radian = textureLoad(radians, gid.xy, 0).r;
+    let dir = vec2f(cos(radian), sin(radian));
+    let p = vec2f(gid.xy);
+
+    // the centre may lie in both directions: bright circle on dark background and vice versa
+    for (var r = params.min_radius; r <= params.max_radius; r++) {
+        for (var sign = -1.; sign <= 1.; sign += 2.) {
+            let centre = vec2i(round(p + sign * f32(r) * dir));
+
+            let is_inside = centre.x >= 0 && centre.x < size.x && centre.y >= 0 && centre.y < size.y;
+            if (is_inside) {
+                atomicAdd(&accumulator[centre.y * size.x + centre.x], 1u);
+            }
+        }
+    }
+}
@@ -0,0 +1,42 @@
+@group(0) @binding(0)
+var edges: texture_2d<f32>;
+
+@group(0) @binding(1)
+var<storage, read_write> accumulator: array<atomic<u32>>;
+
+struct Params {
+    rho_resolution: f32,
+    theta_resolution: f32,
+    num_rho: u32,
+    num_theta: u32,
+};
+
+@group(0) @binding(2)
+var<uniform> params: Params;
+
+const IS_EDGE: f32 = 1.0;
+
+@compute
+@workgroup_size(16, 16, 1)
+fn main(@builtin(global_invocation_id) gid: vec3u) {
+    let size = textureDimensions(edges);
+    if (gid.x >= size.x || gid.y >= size.y) {
+        return;
+    }
+
+    if (textureLoad(edges, gid.xy, 0).r != IS_EDGE) {
+        return;
+    }
+
+    let p = vec2f(gid.xy);
+    // `rho` lies in [-max_rho, max_rho], so shift it into the positive range
+    let max_rho = f32(params.num_rho - 1) * params.rho_resolution * 0.5;
+
+    for (var theta_idx = 0u; theta_idx < params.num_theta; theta_idx++) {
+        let theta = f32(theta_idx) * params.theta_resolution;
+        let rho = p.x * cos(theta) + p.y * sin(theta);
+
+        let rho_idx = u32(round((rho + max_rho) / params.rho_resolution));
+        atomicAdd(&accumulator[theta_idx * params.num_rho + rho_idx], 1u);
+    }
+}
@@ -7,6 +7,14 @@ var output: texture_storage_2d<r32float, write>;
 @group(0) @binding(2)
 var<storage, read> kernel: array<f32>;
 
+struct Roi {
+    origin: vec2u,
+    size: vec2u,
+};
+
+@group(0) @binding(3)
+var<uniform> roi: Roi;
+
 @compute
 @workgroup_size(16, 16, 1)
 fn main(@builtin(global_invocation_id) gid: vec3u) {
@@ -19,7 +27,7 @@ fn main(@builtin(global_invocation_id) gid: vec3u) {
     var sum: f32 = 0.;
     for (var x = -half_kernel_size; x <= half_kernel_size; x++) {
         for (var y = -half_kernel_size; y <= half_kernel_size; y++) {
-            let coords: vec2i = igid + vec2i(x, y);
+            let coords: vec2i = igid + vec2i(roi.origin) + vec2i(x, y);
 
             let is_over_the_top = coords.y < 0;
             let is_over_left_edge = coords.x < 0;
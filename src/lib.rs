@@ -1,21 +1,298 @@
-use wgpu::{include_wgsl, util::DeviceExt};
+pub mod cpu;
+mod encoding;
+mod gpu;
+mod hough;
+pub mod metrics;
+pub mod npy;
+mod params;
+mod pipelines;
+mod profile;
+mod readback;
+pub mod sheet;
+pub mod synthetic;
+mod tiling;
+mod visualization;
+
+pub use encoding::*;
+pub use gpu::*;
+pub use hough::*;
+pub use params::*;
+pub use pipelines::Pipelines;
+pub use profile::*;
+pub use readback::*;
+pub use tiling::*;
+pub use visualization::*;
+
+use wgpu::util::DeviceExt;
 
 pub trait Renderer {
     fn device(&self) -> &wgpu::Device;
 
     fn queue(&self) -> &wgpu::Queue;
+
+    /// Cache for the compiled compute pipelines. Without it, each stage compiles its shaders again.
+    fn pipelines(&self) -> Option<&Pipelines> {
+        None
+    }
+
+    /// Records the durations of the stages, see `Profiler`.
+    fn profiler(&self) -> Option<&Profiler> {
+        None
+    }
+}
+
+/// A rectangle of a texture which should be processed, in pixels.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct Roi {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Roi {
+    pub fn new(x: u32, y: u32, width: u32, height: u32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Returns an error if the ROI is empty or doesn't lie inside of a `width`x`height` texture.
+    pub fn validate(&self, width: u32, height: u32) -> Result<(), CannyError> {
+        let is_inside = self
+            .x
+            .checked_add(self.width)
+            .is_some_and(|end| end <= width)
+            && self
+                .y
+                .checked_add(self.height)
+                .is_some_and(|end| end <= height);
+
+        if self.width == 0 || self.height == 0 || !is_inside {
+            return Err(CannyError::InvalidRoi {
+                roi: *self,
+                width,
+                height,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Grows the ROI by `apron` pixels in each direction without leaving a `width`x`height` texture.
+    pub fn padded(&self, apron: u32, width: u32, height: u32) -> Self {
+        let x = self.x.saturating_sub(apron);
+        let y = self.y.saturating_sub(apron);
+
+        let end_x = self.x.saturating_add(self.width).saturating_add(apron);
+        let end_y = self.y.saturating_add(self.height).saturating_add(apron);
+
+        Self {
+            x,
+            y,
+            width: end_x.min(width).saturating_sub(x),
+            height: end_y.min(height).saturating_sub(y),
+        }
+    }
+
+    /// Returns this ROI relative to the origin of `outer`.
+    ///
+    /// Panics if this ROI starts in front of `outer`.
+    pub fn relative_to(&self, outer: &Roi) -> Self {
+        Self {
+            x: self
+                .x
+                .checked_sub(outer.x)
+                .expect("The ROI starts left of the outer ROI"),
+            y: self
+                .y
+                .checked_sub(outer.y)
+                .expect("The ROI starts above the outer ROI"),
+            ..*self
+        }
+    }
+
+    /// The ROI or, without one, the whole texture.
+    ///
+    /// Panics if the ROI isn't valid for the texture, see `validate`.
+    fn or_whole_texture(roi: Option<Roi>, texture: &wgpu::Texture, stage: &str) -> Self {
+        let Some(roi) = roi else {
+            return Self::new(0, 0, texture.width(), texture.height());
+        };
+
+        if let Err(err) = roi.validate(texture.width(), texture.height()) {
+            panic!("{stage}: {err}");
+        }
+
+        roi
+    }
+
+    fn size(&self) -> wgpu::Extent3d {
+        wgpu::Extent3d {
+            width: self.width,
+            height: self.height,
+            depth_or_array_layers: 1,
+        }
+    }
+
+    fn origin(&self) -> wgpu::Origin3d {
+        wgpu::Origin3d {
+            x: self.x,
+            y: self.y,
+            z: 0,
+        }
+    }
+
+    fn create_buffer(&self, device: &wgpu::Device, label: &str) -> wgpu::Buffer {
+        device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(label),
+            contents: bytemuck::bytes_of(self),
+            usage: wgpu::BufferUsages::UNIFORM,
+        })
+    }
+}
+
+/// Masks (`R8Unorm` textures) to steer the double thresholding and therefore the edge tracking.
+#[derive(Debug, Clone, Default)]
+pub struct EdgeMasks {
+    /// Pixels with the value zero are never an edge.
+    pub ignore: Option<wgpu::TextureView>,
+    /// Pixels with a non-zero value are always treated as strong edges.
+    pub seed: Option<wgpu::TextureView>,
+    /// Position of the thresholded texture inside of the masks.
+    /// Only needed if the masks are bigger than the texture, for example because it's a ROI of the image.
+    pub origin: [u32; 2],
 }
 
-pub fn apply_grayscale(renderer: &dyn Renderer, tv: wgpu::TextureView) -> wgpu::Texture {
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct GpuEdgeMasks {
+    origin: [u32; 2],
+    use_ignore_mask: u32,
+    use_seed_mask: u32,
+}
+
+impl EdgeMasks {
+    fn create_buffer(&self, device: &wgpu::Device) -> wgpu::Buffer {
+        device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Edge masks: Buffer"),
+            contents: bytemuck::bytes_of(&GpuEdgeMasks {
+                origin: self.origin,
+                use_ignore_mask: self.ignore.is_some() as u32,
+                use_seed_mask: self.seed.is_some() as u32,
+            }),
+            usage: wgpu::BufferUsages::UNIFORM,
+        })
+    }
+
+    /// Returns the given mask or a placeholder, because the shaders always need a bound texture.
+    fn view_or_placeholder(
+        device: &wgpu::Device,
+        mask: &Option<wgpu::TextureView>,
+    ) -> wgpu::TextureView {
+        match mask {
+            Some(mask) => mask.clone(),
+            None => device
+                .create_texture(&wgpu::TextureDescriptor {
+                    label: Some("Edge masks: Placeholder texture"),
+                    size: wgpu::Extent3d {
+                        width: 1,
+                        height: 1,
+                        depth_or_array_layers: 1,
+                    },
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: wgpu::TextureDimension::D2,
+                    format: wgpu::TextureFormat::R8Unorm,
+                    usage: wgpu::TextureUsages::TEXTURE_BINDING,
+                    view_formats: &[],
+                })
+                .create_view(&wgpu::TextureViewDescriptor::default()),
+        }
+    }
+}
+
+/// Formats which `apply_grayscale` and `apply_canny` accept as input.
+///
+/// The single channel formats are taken as gray values. `R16Unorm` needs the
+/// `TEXTURE_FORMAT_16BIT_NORM` feature.
+pub const INPUT_FORMATS: [wgpu::TextureFormat; 9] = [
+    wgpu::TextureFormat::Rgba8Unorm,
+    wgpu::TextureFormat::Rgba8UnormSrgb,
+    wgpu::TextureFormat::Bgra8Unorm,
+    wgpu::TextureFormat::Bgra8UnormSrgb,
+    wgpu::TextureFormat::Rgba16Float,
+    wgpu::TextureFormat::Rgba32Float,
+    wgpu::TextureFormat::R8Unorm,
+    wgpu::TextureFormat::R16Unorm,
+    wgpu::TextureFormat::R32Float,
+];
+
+/// Checks if the texture can be the input of `apply_grayscale` and `apply_canny`.
+pub fn validate_input_texture(texture: &wgpu::Texture) -> Result<(), CannyError> {
+    if !INPUT_FORMATS.contains(&texture.format()) {
+        return Err(CannyError::UnsupportedFormat(texture.format()));
+    }
+    if !texture
+        .usage()
+        .contains(wgpu::TextureUsages::TEXTURE_BINDING)
+    {
+        return Err(CannyError::MissingUsage(
+            wgpu::TextureUsages::TEXTURE_BINDING,
+        ));
+    }
+
+    Ok(())
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+pub(crate) struct GpuInputFormat {
+    single_channel: u32,
+    srgb: u32,
+}
+
+impl GpuInputFormat {
+    pub(crate) fn new(format: wgpu::TextureFormat) -> Self {
+        Self {
+            single_channel: (format.components() == 1) as u32,
+            srgb: format.is_srgb() as u32,
+        }
+    }
+}
+
+/// Converts the input into a `R32Float` texture of gray values.
+///
+/// Returns an error if the input or the ROI isn't valid, see `validate_input_texture` and
+/// `Roi::validate`.
+pub fn apply_grayscale(
+    renderer: &dyn Renderer,
+    tv: wgpu::TextureView,
+    roi: Option<Roi>,
+) -> Result<wgpu::Texture, CannyError> {
     const WORKGROUP_SIZE: u32 = 16;
 
     let device = renderer.device();
-    let queue = renderer.queue();
 
     let in_texture = tv.texture();
+    validate_input_texture(in_texture)?;
+
+    let roi = roi.unwrap_or_else(|| Roi::new(0, 0, in_texture.width(), in_texture.height()));
+    roi.validate(in_texture.width(), in_texture.height())?;
+    let roi_buffer = roi.create_buffer(device, "Gray scale: ROI buffer");
+    let format_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Gray scale: Input format buffer"),
+        contents: bytemuck::bytes_of(&GpuInputFormat::new(in_texture.format())),
+        usage: wgpu::BufferUsages::UNIFORM,
+    });
+
     let out_texture = device.create_texture(&wgpu::TextureDescriptor {
         label: Some("Gray scale: Output texture"),
-        size: in_texture.size(),
+        size: roi.size(),
         mip_level_count: 1,
         sample_count: 1,
         dimension: in_texture.dimension(),
@@ -26,18 +303,7 @@ pub fn apply_grayscale(renderer: &dyn Renderer, tv: wgpu::TextureView) -> wgpu::
         view_formats: &[],
     });
 
-    let pipeline = {
-        let shader = device.create_shader_module(include_wgsl!("./grayscale.wgsl"));
-
-        device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-            label: Some("Grayscale: Compute pipeline"),
-            layout: None,
-            module: &shader,
-            entry_point: None,
-            compilation_options: wgpu::PipelineCompilationOptions::default(),
-            cache: None,
-        })
-    };
+    let pipeline = Pipelines::grayscale(renderer);
 
     let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
         label: Some("Gray scale: Bind group 0"),
@@ -53,6 +319,14 @@ pub fn apply_grayscale(renderer: &dyn Renderer, tv: wgpu::TextureView) -> wgpu::
                     &out_texture.create_view(&wgpu::TextureViewDescriptor::default()),
                 ),
             },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: roi_buffer.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 3,
+                resource: format_buffer.as_entire_binding(),
+            },
         ],
     });
 
@@ -60,34 +334,41 @@ pub fn apply_grayscale(renderer: &dyn Renderer, tv: wgpu::TextureView) -> wgpu::
     {
         let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
             label: Some("Gray scale: Compute pass"),
-            timestamp_writes: None,
+            timestamp_writes: profile::timestamp_writes(renderer, "gray scale"),
         });
 
         pass.set_bind_group(0, &bind_group, &[]);
         pass.set_pipeline(&pipeline);
         pass.dispatch_workgroups(
-            in_texture.width().div_ceil(WORKGROUP_SIZE),
-            in_texture.height().div_ceil(WORKGROUP_SIZE),
+            roi.width.div_ceil(WORKGROUP_SIZE),
+            roi.height.div_ceil(WORKGROUP_SIZE),
             1,
         );
     }
 
-    queue.submit(std::iter::once(encoder.finish()));
+    profile::submit(renderer, "gray scale", encoder);
 
-    out_texture
+    Ok(out_texture)
 }
 
-pub fn apply_gaussian_filter(renderer: &dyn Renderer, tv: wgpu::TextureView) -> wgpu::Texture {
+pub fn apply_gaussian_filter(
+    renderer: &dyn Renderer,
+    tv: wgpu::TextureView,
+    sigma: f32,
+    kernel_radius: u32,
+    roi: Option<Roi>,
+) -> wgpu::Texture {
     const WORKGROUP_SIZE: u32 = 16;
 
     let device = renderer.device();
-    let queue = renderer.queue();
 
     let in_texture = tv.texture();
+    let roi = Roi::or_whole_texture(roi, in_texture, "Gaussian filter");
+    let roi_buffer = roi.create_buffer(device, "Gaussian filter: ROI buffer");
 
     let out_texture = device.create_texture(&wgpu::TextureDescriptor {
         label: Some("Gaussian filter: Output tetxure"),
-        size: in_texture.size(),
+        size: roi.size(),
         mip_level_count: in_texture.mip_level_count(),
         sample_count: in_texture.sample_count(),
         dimension: in_texture.dimension(),
@@ -98,34 +379,7 @@ pub fn apply_gaussian_filter(renderer: &dyn Renderer, tv: wgpu::TextureView) ->
         view_formats: &[],
     });
 
-    let kernel = {
-        fn gauss(sigma: f32, x: f32, y: f32) -> f32 {
-            (1. / (2. * std::f32::consts::PI * sigma * sigma))
-                * std::f32::consts::E.powf(-(x * x + y * y) / (2. * sigma * sigma))
-        }
-
-        let sigma = 1.6;
-        let kernel_size = 10isize;
-        let half_kernel_size = kernel_size / 2;
-        let mut kernel: Vec<f32> = Vec::with_capacity((kernel_size * kernel_size) as usize);
-
-        let mut total_sum = 0.;
-        for x in (-half_kernel_size)..=half_kernel_size {
-            for y in (-half_kernel_size)..=half_kernel_size {
-                let value = gauss(sigma, x as f32, y as f32);
-                kernel.push(value);
-
-                total_sum += value;
-            }
-        }
-
-        // normalize kernel
-        for value in kernel.iter_mut() {
-            *value /= total_sum;
-        }
-
-        kernel
-    };
+    let kernel = gaussian_kernel(sigma, kernel_radius);
 
     let kernel_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
         label: Some("Gaussian filter: Kernel buffer"),
@@ -133,18 +387,7 @@ pub fn apply_gaussian_filter(renderer: &dyn Renderer, tv: wgpu::TextureView) ->
         usage: wgpu::BufferUsages::STORAGE,
     });
 
-    let pipeline = {
-        let shader = device.create_shader_module(include_wgsl!("./kernels.wgsl"));
-
-        device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-            label: Some("Gaussian filter pipeline"),
-            layout: None,
-            module: &shader,
-            entry_point: None,
-            compilation_options: wgpu::PipelineCompilationOptions::default(),
-            cache: None,
-        })
-    };
+    let pipeline = Pipelines::gaussian_filter(renderer);
 
     let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
         label: Some("Gaussian filter: Bind group 0"),
@@ -166,6 +409,10 @@ pub fn apply_gaussian_filter(renderer: &dyn Renderer, tv: wgpu::TextureView) ->
                 binding: 2,
                 resource: kernel_buffer.as_entire_binding(),
             },
+            wgpu::BindGroupEntry {
+                binding: 3,
+                resource: roi_buffer.as_entire_binding(),
+            },
         ],
     });
 
@@ -173,36 +420,68 @@ pub fn apply_gaussian_filter(renderer: &dyn Renderer, tv: wgpu::TextureView) ->
     {
         let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
             label: Some("Gaussian filter: Compute pass"),
-            timestamp_writes: None,
+            timestamp_writes: profile::timestamp_writes(renderer, "gaussian"),
         });
 
         pass.set_bind_group(0, &bind_group, &[]);
         pass.set_pipeline(&pipeline);
         pass.dispatch_workgroups(
-            in_texture.width().div_ceil(WORKGROUP_SIZE),
-            in_texture.height().div_ceil(WORKGROUP_SIZE),
+            roi.width.div_ceil(WORKGROUP_SIZE),
+            roi.height.div_ceil(WORKGROUP_SIZE),
             1,
         );
     }
 
-    queue.submit(std::iter::once(encoder.finish()));
+    profile::submit(renderer, "gaussian", encoder);
     out_texture
 }
 
+/// Returns the normalized `(2 * kernel_radius + 1)²` gaussian kernel.
+pub(crate) fn gaussian_kernel(sigma: f32, kernel_radius: u32) -> Vec<f32> {
+    fn gauss(sigma: f32, x: f32, y: f32) -> f32 {
+        (1. / (2. * std::f32::consts::PI * sigma * sigma))
+            * std::f32::consts::E.powf(-(x * x + y * y) / (2. * sigma * sigma))
+    }
+
+    let half_kernel_size = kernel_radius as isize;
+    let kernel_size = 2 * half_kernel_size + 1;
+    let mut kernel: Vec<f32> = Vec::with_capacity((kernel_size * kernel_size) as usize);
+
+    let mut total_sum = 0.;
+    for x in (-half_kernel_size)..=half_kernel_size {
+        for y in (-half_kernel_size)..=half_kernel_size {
+            let value = gauss(sigma, x as f32, y as f32);
+            kernel.push(value);
+
+            total_sum += value;
+        }
+    }
+
+    // normalize kernel
+    for value in kernel.iter_mut() {
+        *value /= total_sum;
+    }
+
+    kernel
+}
+
 pub fn apply_sobel_operators(
     renderer: &dyn Renderer,
     tv: wgpu::TextureView,
+    operator: GradientOperator,
+    roi: Option<Roi>,
 ) -> (wgpu::Texture, wgpu::Texture) {
     const WORKGROUP_SIZE: u32 = 16;
 
     let device = renderer.device();
-    let queue = renderer.queue();
 
     let texture = tv.texture();
+    let roi = Roi::or_whole_texture(roi, texture, "Sobel");
+    let roi_buffer = roi.create_buffer(device, "Soeber: ROI buffer");
 
     let vertical_texture = device.create_texture(&wgpu::TextureDescriptor {
         label: Some("Vertical soeber: Output texture"),
-        size: texture.size(),
+        size: roi.size(),
         mip_level_count: texture.mip_level_count(),
         sample_count: texture.sample_count(),
         dimension: texture.dimension(),
@@ -215,7 +494,7 @@ pub fn apply_sobel_operators(
 
     let horizontal_texture = device.create_texture(&wgpu::TextureDescriptor {
         label: Some("Horizontal soeber: Output texture"),
-        size: texture.size(),
+        size: roi.size(),
         mip_level_count: texture.mip_level_count(),
         sample_count: texture.sample_count(),
         dimension: texture.dimension(),
@@ -226,46 +505,24 @@ pub fn apply_sobel_operators(
         view_formats: &[],
     });
 
-    let (vertical_pipeline, horizontal_pipeline) = {
-        let shader = device.create_shader_module(include_wgsl!("./kernels.wgsl"));
-
-        let vertical_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-            label: Some("Vertical Soeber: Compute pipeline"),
-            layout: None,
-            module: &shader,
-            entry_point: None,
-            compilation_options: wgpu::PipelineCompilationOptions::default(),
-            cache: None,
-        });
-
-        let horizontal_pipeline =
-            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-                label: Some("Horizontal Soeber: Compute pipeline"),
-                layout: None,
-                module: &shader,
-                entry_point: None,
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-                cache: None,
-            });
-
-        (vertical_pipeline, horizontal_pipeline)
-    };
+    let pipeline = Pipelines::sobel_operators(renderer);
+    let (vertical_kernel, horizontal_kernel) = operator.kernels();
 
     let vertical_kernel_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
         label: Some("Vertical Soeber: Kernel"),
-        contents: bytemuck::cast_slice(&[-1f32, -2., -1., 0., 0., 0., 1., 2., 1.]),
+        contents: bytemuck::cast_slice(&vertical_kernel),
         usage: wgpu::BufferUsages::STORAGE,
     });
 
     let horizontal_kernel_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
         label: Some("Horizontal Soeber: Kernel"),
-        contents: bytemuck::cast_slice(&[-1f32, 0., 1., -2., 0., 2., -1., 0., 1.]),
+        contents: bytemuck::cast_slice(&horizontal_kernel),
         usage: wgpu::BufferUsages::STORAGE,
     });
 
     let vertical_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
         label: Some("Vertical Soeber: Bind group 0"),
-        layout: &vertical_pipeline.get_bind_group_layout(0),
+        layout: &pipeline.get_bind_group_layout(0),
         entries: &[
             wgpu::BindGroupEntry {
                 binding: 0,
@@ -281,12 +538,16 @@ pub fn apply_sobel_operators(
                 binding: 2,
                 resource: vertical_kernel_buffer.as_entire_binding(),
             },
+            wgpu::BindGroupEntry {
+                binding: 3,
+                resource: roi_buffer.as_entire_binding(),
+            },
         ],
     });
 
     let horizontal_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
         label: Some("Horizontal Soeber: Bind group 0"),
-        layout: &horizontal_pipeline.get_bind_group_layout(0),
+        layout: &pipeline.get_bind_group_layout(0),
         entries: &[
             wgpu::BindGroupEntry {
                 binding: 0,
@@ -302,29 +563,33 @@ pub fn apply_sobel_operators(
                 binding: 2,
                 resource: horizontal_kernel_buffer.as_entire_binding(),
             },
+            wgpu::BindGroupEntry {
+                binding: 3,
+                resource: roi_buffer.as_entire_binding(),
+            },
         ],
     });
 
     let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
     {
-        let dispatch_workgroups_x = texture.width().div_ceil(WORKGROUP_SIZE);
-        let dispatch_workgroups_y = texture.height().div_ceil(WORKGROUP_SIZE);
+        let dispatch_workgroups_x = roi.width.div_ceil(WORKGROUP_SIZE);
+        let dispatch_workgroups_y = roi.height.div_ceil(WORKGROUP_SIZE);
 
         let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
             label: Some("Soeber: Compute pass"),
-            timestamp_writes: None,
+            timestamp_writes: profile::timestamp_writes(renderer, "gradients"),
         });
 
+        pass.set_pipeline(&pipeline);
+
         pass.set_bind_group(0, &vertical_bind_group, &[]);
-        pass.set_pipeline(&vertical_pipeline);
         pass.dispatch_workgroups(dispatch_workgroups_x, dispatch_workgroups_y, 1);
 
         pass.set_bind_group(0, &horizontal_bind_group, &[]);
-        pass.set_pipeline(&horizontal_pipeline);
         pass.dispatch_workgroups(dispatch_workgroups_x, dispatch_workgroups_y, 1);
     }
 
-    queue.submit(std::iter::once(encoder.finish()));
+    profile::submit(renderer, "gradients", encoder);
 
     (horizontal_texture, vertical_texture)
 }
@@ -333,17 +598,19 @@ pub fn apply_magnitude_and_angle(
     renderer: &dyn Renderer,
     vertical: wgpu::TextureView,
     horizontal: wgpu::TextureView,
+    roi: Option<Roi>,
 ) -> (wgpu::Texture, wgpu::Texture) {
     const WORKGROUP_SIZE: u32 = 16;
 
     let device = renderer.device();
-    let queue = renderer.queue();
 
     let vtexture = vertical.texture();
+    let roi = Roi::or_whole_texture(roi, vtexture, "Magnitude");
+    let roi_buffer = roi.create_buffer(device, "Magnitude: ROI buffer");
 
     let magnitude_texture = device.create_texture(&wgpu::TextureDescriptor {
         label: Some("Magnitude: Texture"),
-        size: vtexture.size(),
+        size: roi.size(),
         mip_level_count: vtexture.mip_level_count(),
         sample_count: vtexture.sample_count(),
         dimension: vtexture.dimension(),
@@ -356,7 +623,7 @@ pub fn apply_magnitude_and_angle(
 
     let radians_texture = device.create_texture(&wgpu::TextureDescriptor {
         label: Some("Radians: Texture"),
-        size: vtexture.size(),
+        size: roi.size(),
         mip_level_count: vtexture.mip_level_count(),
         sample_count: vtexture.sample_count(),
         dimension: vtexture.dimension(),
@@ -367,18 +634,7 @@ pub fn apply_magnitude_and_angle(
         view_formats: &[],
     });
 
-    let pipeline = {
-        let shader = device.create_shader_module(include_wgsl!("./magnitude.wgsl"));
-
-        device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-            label: Some("Magnitude: Compute pipeline"),
-            layout: None,
-            module: &shader,
-            entry_point: None,
-            compilation_options: wgpu::PipelineCompilationOptions::default(),
-            cache: None,
-        })
-    };
+    let pipeline = Pipelines::magnitude(renderer);
 
     let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
         label: Some("Magnitude: Bind group"),
@@ -404,6 +660,10 @@ pub fn apply_magnitude_and_angle(
                     &radians_texture.create_view(&wgpu::TextureViewDescriptor::default()),
                 ),
             },
+            wgpu::BindGroupEntry {
+                binding: 4,
+                resource: roi_buffer.as_entire_binding(),
+            },
         ],
     });
 
@@ -414,19 +674,19 @@ pub fn apply_magnitude_and_angle(
     {
         let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
             label: Some("Magnitude: Compute pass"),
-            timestamp_writes: None,
+            timestamp_writes: profile::timestamp_writes(renderer, "magnitude"),
         });
 
         pass.set_bind_group(0, &bind_group, &[]);
         pass.set_pipeline(&pipeline);
         pass.dispatch_workgroups(
-            vtexture.width().div_ceil(WORKGROUP_SIZE),
-            vtexture.height().div_ceil(WORKGROUP_SIZE),
+            roi.width.div_ceil(WORKGROUP_SIZE),
+            roi.height.div_ceil(WORKGROUP_SIZE),
             1,
         );
     }
 
-    queue.submit(std::iter::once(encoder.finish()));
+    profile::submit(renderer, "magnitude", encoder);
 
     (magnitude_texture, radians_texture)
 }
@@ -435,17 +695,26 @@ pub fn apply_non_maximum_suppression(
     renderer: &dyn Renderer,
     magnitudes: wgpu::TextureView,
     radians: wgpu::TextureView,
+    mode: NmsMode,
+    roi: Option<Roi>,
 ) -> wgpu::Texture {
     const WORKGROUP_SIZE: u32 = 16;
 
     let device = renderer.device();
-    let queue = renderer.queue();
 
     let m_texture = magnitudes.texture();
+    let roi = Roi::or_whole_texture(roi, m_texture, "Non maximum suppression");
+    let roi_buffer = roi.create_buffer(device, "Non maximum suppression: ROI buffer");
+
+    let mode_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Non maximum suppression: Mode buffer"),
+        contents: bytemuck::bytes_of(&(mode as u32)),
+        usage: wgpu::BufferUsages::UNIFORM,
+    });
 
     let out_texture = device.create_texture(&wgpu::TextureDescriptor {
         label: Some("Non maximum suppression: Texture"),
-        size: m_texture.size(),
+        size: roi.size(),
         mip_level_count: m_texture.mip_level_count(),
         sample_count: m_texture.sample_count(),
         dimension: m_texture.dimension(),
@@ -456,18 +725,7 @@ pub fn apply_non_maximum_suppression(
         view_formats: &[],
     });
 
-    let pipeline = {
-        let shader = device.create_shader_module(include_wgsl!("./non_maximum_suppression.wgsl"));
-
-        device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-            label: Some("Non maximum suppression: Compute pipeline"),
-            layout: None,
-            module: &shader,
-            entry_point: None,
-            compilation_options: wgpu::PipelineCompilationOptions::default(),
-            cache: None,
-        })
-    };
+    let pipeline = Pipelines::non_maximum_suppression(renderer);
 
     let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
         label: Some("Non maximum suppression: Bind group"),
@@ -487,6 +745,14 @@ pub fn apply_non_maximum_suppression(
                     &out_texture.create_view(&wgpu::TextureViewDescriptor::default()),
                 ),
             },
+            wgpu::BindGroupEntry {
+                binding: 3,
+                resource: roi_buffer.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 4,
+                resource: mode_buffer.as_entire_binding(),
+            },
         ],
     });
 
@@ -497,19 +763,19 @@ pub fn apply_non_maximum_suppression(
     {
         let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
             label: Some("Non maximum suppression: Compute pass"),
-            timestamp_writes: None,
+            timestamp_writes: profile::timestamp_writes(renderer, "non maximum suppression"),
         });
 
         pass.set_bind_group(0, &bind_group, &[]);
         pass.set_pipeline(&pipeline);
         pass.dispatch_workgroups(
-            m_texture.width().div_ceil(WORKGROUP_SIZE),
-            m_texture.height().div_ceil(WORKGROUP_SIZE),
+            roi.width.div_ceil(WORKGROUP_SIZE),
+            roi.height.div_ceil(WORKGROUP_SIZE),
             1,
         );
     }
 
-    queue.submit(std::iter::once(encoder.finish()));
+    profile::submit(renderer, "non maximum suppression", encoder);
 
     out_texture
 }
@@ -517,17 +783,26 @@ pub fn apply_non_maximum_suppression(
 pub fn apply_double_thresholding(
     renderer: &dyn Renderer,
     non_maximum_suppression: wgpu::TextureView,
+    thresholds: Thresholds,
+    roi: Option<Roi>,
+    masks: &EdgeMasks,
+    max_value: Option<f32>,
 ) -> wgpu::Texture {
     const WORKGROUP_SIZE: u32 = 16;
 
     let device = renderer.device();
-    let queue = renderer.queue();
 
     let nms_texture = non_maximum_suppression.texture();
+    let roi = Roi::or_whole_texture(roi, nms_texture, "Double thresholding");
+    let roi_buffer = roi.create_buffer(device, "Double Threshold: ROI buffer");
+
+    let masks_buffer = masks.create_buffer(device);
+    let ignore_mask = EdgeMasks::view_or_placeholder(device, &masks.ignore);
+    let seed_mask = EdgeMasks::view_or_placeholder(device, &masks.seed);
 
     let out_texture = device.create_texture(&wgpu::TextureDescriptor {
         label: Some("Double Threshold: Output texture"),
-        size: nms_texture.size(),
+        size: roi.size(),
         mip_level_count: nms_texture.mip_level_count(),
         sample_count: nms_texture.mip_level_count(),
         dimension: nms_texture.dimension(),
@@ -540,39 +815,20 @@ pub fn apply_double_thresholding(
 
     let threshold_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
         label: Some("Double Threshold: Threshold buffer"),
-        contents: bytemuck::cast_slice(&[0.2f32, 0.7]),
+        contents: bytemuck::cast_slice(&[thresholds.high, thresholds.low]),
         usage: wgpu::BufferUsages::UNIFORM,
     });
 
-    let max_value = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+    // same encoding as in `max_value.wgsl`
+    let encoded_max_value = max_value.map_or(0u32, |value| (value * u32::MAX as f32) as u32);
+    let max_value_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
         label: Some("Double Threshold: Max value buffer"),
-        contents: bytemuck::bytes_of(&0u32),
+        contents: bytemuck::bytes_of(&encoded_max_value),
         usage: wgpu::BufferUsages::STORAGE,
     });
 
-    let max_value_pipeline = {
-        let shader = device.create_shader_module(include_wgsl!("./max_value.wgsl"));
-        device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-            label: Some("Double Threshold: Max value pipeline"),
-            layout: None,
-            module: &shader,
-            entry_point: None,
-            compilation_options: wgpu::PipelineCompilationOptions::default(),
-            cache: None,
-        })
-    };
-
-    let threshold_pipeline = {
-        let shader = device.create_shader_module(include_wgsl!("./double_threshoulding.wgsl"));
-        device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-            label: Some("Double Threshold: Compute pipeline"),
-            layout: None,
-            module: &shader,
-            entry_point: None,
-            compilation_options: wgpu::PipelineCompilationOptions::default(),
-            cache: None,
-        })
-    };
+    let max_value_pipeline = Pipelines::max_value(renderer);
+    let threshold_pipeline = Pipelines::double_thresholding(renderer);
 
     let max_value_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
         label: Some("Double Threshold: Max value bind group"),
@@ -584,7 +840,19 @@ pub fn apply_double_thresholding(
             },
             wgpu::BindGroupEntry {
                 binding: 1,
-                resource: max_value.as_entire_binding(),
+                resource: max_value_buffer.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: roi_buffer.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 3,
+                resource: wgpu::BindingResource::TextureView(&ignore_mask),
+            },
+            wgpu::BindGroupEntry {
+                binding: 4,
+                resource: masks_buffer.as_entire_binding(),
             },
         ],
     });
@@ -605,12 +873,28 @@ pub fn apply_double_thresholding(
             },
             wgpu::BindGroupEntry {
                 binding: 2,
-                resource: max_value.as_entire_binding(),
+                resource: max_value_buffer.as_entire_binding(),
             },
             wgpu::BindGroupEntry {
                 binding: 3,
                 resource: threshold_buffer.as_entire_binding(),
             },
+            wgpu::BindGroupEntry {
+                binding: 4,
+                resource: roi_buffer.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 5,
+                resource: wgpu::BindingResource::TextureView(&ignore_mask),
+            },
+            wgpu::BindGroupEntry {
+                binding: 6,
+                resource: wgpu::BindingResource::TextureView(&seed_mask),
+            },
+            wgpu::BindGroupEntry {
+                binding: 7,
+                resource: masks_buffer.as_entire_binding(),
+            },
         ],
     });
 
@@ -619,87 +903,610 @@ pub fn apply_double_thresholding(
     {
         let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
             label: Some("Doule Threshold: Compute pass"),
-            timestamp_writes: None,
+            timestamp_writes: profile::timestamp_writes(renderer, "double thresholding"),
         });
 
-        // set `max_value` first
-        pass.set_bind_group(0, &max_value_bind_group, &[]);
-        pass.set_pipeline(&max_value_pipeline);
-        pass.dispatch_workgroups(
-            nms_texture.width().div_ceil(WORKGROUP_SIZE),
-            nms_texture.height().div_ceil(WORKGROUP_SIZE),
-            1,
-        );
+        // set `max_value` first, if it isn't given
+        if max_value.is_none() {
+            pass.set_bind_group(0, &max_value_bind_group, &[]);
+            pass.set_pipeline(&max_value_pipeline);
+            pass.dispatch_workgroups(
+                roi.width.div_ceil(WORKGROUP_SIZE),
+                roi.height.div_ceil(WORKGROUP_SIZE),
+                1,
+            );
+        }
 
         // now apply thresholds
         pass.set_bind_group(0, &double_threshold_bind_group, &[]);
         pass.set_pipeline(&threshold_pipeline);
         pass.dispatch_workgroups(
-            nms_texture.width().div_ceil(WORKGROUP_SIZE),
-            nms_texture.height().div_ceil(WORKGROUP_SIZE),
+            roi.width.div_ceil(WORKGROUP_SIZE),
+            roi.height.div_ceil(WORKGROUP_SIZE),
             1,
         );
     }
 
-    queue.submit(std::iter::once(encoder.finish()));
+    profile::submit(renderer, "double thresholding", encoder);
 
     out_texture
 }
 
+/// Promotes every weak edge which is (8-)connected to an edge to an edge.
+///
+/// The edge tracking works in place and in rounds: each workgroup follows the weak edges through its
+/// tile of 16x16 pixels until nothing changes anymore, taking the border of the tile from the last
+/// round. A round is only dispatched if the one before promoted a weak edge, which is decided on the
+/// GPU, so there's no readback. The amount of rounds is limited by `edge_tracking_rounds`, which
+/// lets a chain of weak edges cross the image twice. Longer chains, like spirals, are cut off.
 pub fn apply_edge_tracking(
     renderer: &dyn Renderer,
     double_thresholding: wgpu::TextureView,
+    roi: Option<Roi>,
 ) -> wgpu::Texture {
-    const WORKGROUP_SIZE: u32 = 16;
+    const WORKGROUP_SIZE: u32 = EDGE_TRACKING_TILE_SIZE;
 
     let device = renderer.device();
-    let queue = renderer.queue();
 
-    let texture = double_thresholding.texture().clone();
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
 
-    let pipeline = {
-        let shader = device.create_shader_module(include_wgsl!("./edge_tracking.wgsl"));
+    // edge tracking works in place, so the ROI has to be cut out into its own texture first
+    let (texture, double_thresholding) = match roi {
+        None => (double_thresholding.texture().clone(), double_thresholding),
+        Some(roi) => {
+            let in_texture = double_thresholding.texture();
+            let roi = Roi::or_whole_texture(Some(roi), in_texture, "Edge tracking");
 
-        device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-            label: Some("Edge tracking: Compute pipeline"),
-            layout: None,
-            module: &shader,
-            entry_point: None,
-            compilation_options: wgpu::PipelineCompilationOptions::default(),
-            cache: None,
-        })
+            let texture = copy_roi(device, &mut encoder, in_texture, roi, "Edge tracking");
+            let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+            (texture, view)
+        }
     };
 
+    let pipeline = Pipelines::edge_tracking(renderer);
+    let dispatch_pipeline = Pipelines::edge_tracking_dispatch(renderer);
+
+    let changed_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Edge tracking: Changed buffer"),
+        size: std::mem::size_of::<u32>() as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::STORAGE,
+        mapped_at_creation: false,
+    });
+
+    let workgroups = [
+        texture.width().div_ceil(WORKGROUP_SIZE),
+        texture.height().div_ceil(WORKGROUP_SIZE),
+        1,
+    ];
+    let dispatch_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Edge tracking: Dispatch buffer"),
+        contents: bytemuck::cast_slice(&workgroups),
+        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::INDIRECT,
+    });
+
     let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
         label: Some("Edge tracking: Bind group"),
         layout: &pipeline.get_bind_group_layout(0),
-        entries: &[wgpu::BindGroupEntry {
-            binding: 0,
-            resource: wgpu::BindingResource::TextureView(&double_thresholding),
-        }],
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&double_thresholding),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: changed_buffer.as_entire_binding(),
+            },
+        ],
     });
 
-    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    let dispatch_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Edge tracking: Dispatch bind group"),
+        layout: &dispatch_pipeline.get_bind_group_layout(0),
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: changed_buffer.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: dispatch_buffer.as_entire_binding(),
+            },
+        ],
+    });
 
     {
         let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
             label: Some("Edge tracking: Compute pass"),
-            timestamp_writes: None,
+            timestamp_writes: profile::timestamp_writes(renderer, "edge tracking"),
         });
 
-        pass.set_bind_group(0, &bind_group, &[]);
-        pass.set_pipeline(&pipeline);
+        for _ in 0..edge_tracking_rounds(texture.width(), texture.height()) {
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.set_pipeline(&pipeline);
+            pass.dispatch_workgroups_indirect(&dispatch_buffer, 0);
 
-        for _ in 0..10 {
-            pass.dispatch_workgroups(
-                texture.width().div_ceil(WORKGROUP_SIZE),
-                texture.height().div_ceil(WORKGROUP_SIZE),
-                1,
-            );
+            pass.set_bind_group(0, &dispatch_bind_group, &[]);
+            pass.set_pipeline(&dispatch_pipeline);
+            pass.dispatch_workgroups(1, 1, 1);
         }
     }
 
-    queue.submit(std::iter::once(encoder.finish()));
+    profile::submit(renderer, "edge tracking", encoder);
 
     texture
 }
+
+/// Copies the ROI of the texture into a new texture of the same format.
+fn copy_roi(
+    device: &wgpu::Device,
+    encoder: &mut wgpu::CommandEncoder,
+    texture: &wgpu::Texture,
+    roi: Roi,
+    stage: &str,
+) -> wgpu::Texture {
+    let roi_texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some(&format!("{stage}: Output texture")),
+        size: roi.size(),
+        mip_level_count: texture.mip_level_count(),
+        sample_count: texture.sample_count(),
+        dimension: texture.dimension(),
+        format: texture.format(),
+        usage: wgpu::TextureUsages::STORAGE_BINDING
+            | wgpu::TextureUsages::COPY_SRC
+            | wgpu::TextureUsages::COPY_DST
+            | wgpu::TextureUsages::TEXTURE_BINDING,
+        view_formats: &[],
+    });
+
+    encoder.copy_texture_to_texture(
+        wgpu::TexelCopyTextureInfo {
+            texture,
+            mip_level: 0,
+            origin: roi.origin(),
+            aspect: wgpu::TextureAspect::All,
+        },
+        wgpu::TexelCopyTextureInfo {
+            texture: &roi_texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d::ZERO,
+            aspect: wgpu::TextureAspect::All,
+        },
+        roi.size(),
+    );
+
+    roi_texture
+}
+
+/// Width and height of the tiles in which `apply_edge_tracking` follows the weak edges per round.
+pub(crate) const EDGE_TRACKING_TILE_SIZE: u32 = 16;
+
+/// The maximum amount of rounds of `apply_edge_tracking` for a texture of the given size.
+///
+/// Each round carries the edges at least into the next tile, so a chain of weak edges may cross the
+/// tiles of the image twice, horizontally and vertically. The rounds after the last one which
+/// changed something are empty dispatches.
+pub fn edge_tracking_rounds(width: u32, height: u32) -> u32 {
+    2 * (width.div_ceil(EDGE_TRACKING_TILE_SIZE) + height.div_ceil(EDGE_TRACKING_TILE_SIZE))
+}
+
+/// Amount of pixels around a ROI in which `apply_canny` follows weak edges to a strong edge.
+///
+/// Chains of weak edges can be arbitrarily long, so this is a trade off between the work outside
+/// of the ROI and the edges which are lost.
+pub const ROI_EDGE_TRACKING_APRON: u32 = 10;
+
+/// Applies every step of the canny edge detection and returns the final edge texture.
+///
+/// If a ROI is given, only that region is processed and returned. The stages are run on a ROI which
+/// is padded by the apron of the kernels and `ROI_EDGE_TRACKING_APRON` more pixels, so the result
+/// matches the same region of a run over the whole image, except that:
+/// - the thresholds are relative to the strongest edge inside of the padded ROI, and
+/// - weak edges are only kept if they are connected to a strong edge inside of the padded ROI. The
+///   edge tracking runs over the whole padded ROI, so the chains may leave the ROI on their way.
+///
+/// The masks have to cover the whole input texture. Returns an error if the input or the ROI isn't
+/// valid, see `validate_input_texture` and `Roi::validate`.
+pub fn apply_canny(
+    renderer: &dyn Renderer,
+    tv: wgpu::TextureView,
+    params: &CannyParams,
+    roi: Option<Roi>,
+    masks: &EdgeMasks,
+) -> Result<wgpu::Texture, CannyError> {
+    let apron = params.apron() + ROI_EDGE_TRACKING_APRON;
+
+    let in_texture = tv.texture();
+    let (padded_roi, inner_roi) = match roi {
+        Some(roi) => {
+            roi.validate(in_texture.width(), in_texture.height())?;
+            let padded_roi = roi.padded(apron, in_texture.width(), in_texture.height());
+            (Some(padded_roi), Some(roi.relative_to(&padded_roi)))
+        }
+        None => (None, None),
+    };
+
+    let non_maximum_suppression =
+        apply_until_non_maximum_suppression(renderer, tv, params, padded_roi)?;
+
+    // the masks cover the whole input, but everything after the gray scaling only covers the padded ROI
+    let masks = match padded_roi {
+        Some(padded_roi) => &EdgeMasks {
+            origin: [
+                masks.origin[0] + padded_roi.x,
+                masks.origin[1] + padded_roi.y,
+            ],
+            ..masks.clone()
+        },
+        None => masks,
+    };
+
+    let threshold_texture = apply_double_thresholding(
+        renderer,
+        non_maximum_suppression.create_view(&wgpu::TextureViewDescriptor::default()),
+        params.thresholds,
+        None,
+        masks,
+        None,
+    );
+
+    // the weak edges are followed through the apron, only the ROI is cut out afterwards
+    let edges = apply_edge_tracking(
+        renderer,
+        threshold_texture.create_view(&wgpu::TextureViewDescriptor::default()),
+        None,
+    );
+
+    Ok(match inner_roi {
+        Some(inner_roi) => {
+            let device = renderer.device();
+            let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Canny: Command encoder"),
+            });
+            let roi_edges = copy_roi(device, &mut encoder, &edges, inner_roi, "Canny");
+            renderer.queue().submit(std::iter::once(encoder.finish()));
+
+            roi_edges
+        }
+        None => edges,
+    })
+}
+
+/// Applies every step up to (and including) the non maximum suppression.
+pub(crate) fn apply_until_non_maximum_suppression(
+    renderer: &dyn Renderer,
+    tv: wgpu::TextureView,
+    params: &CannyParams,
+    roi: Option<Roi>,
+) -> Result<wgpu::Texture, CannyError> {
+    validate_input_texture(tv.texture())?;
+
+    // a `R32Float` input already has the format of the gray scaling
+    let gray_scale = match roi {
+        None if tv.texture().format() == wgpu::TextureFormat::R32Float => tv.texture().clone(),
+        _ => apply_grayscale(renderer, tv, roi)?,
+    };
+
+    let gaussian = apply_gaussian_filter(
+        renderer,
+        gray_scale.create_view(&wgpu::TextureViewDescriptor::default()),
+        params.sigma,
+        params.kernel_radius,
+        None,
+    );
+
+    let (horizontal, vertical) = apply_sobel_operators(
+        renderer,
+        gaussian.create_view(&wgpu::TextureViewDescriptor::default()),
+        params.gradient_operator,
+        None,
+    );
+
+    let (magnitudes, radians) = apply_magnitude_and_angle(
+        renderer,
+        vertical.create_view(&wgpu::TextureViewDescriptor::default()),
+        horizontal.create_view(&wgpu::TextureViewDescriptor::default()),
+        None,
+    );
+
+    Ok(apply_non_maximum_suppression(
+        renderer,
+        magnitudes.create_view(&wgpu::TextureViewDescriptor::default()),
+        radians.create_view(&wgpu::TextureViewDescriptor::default()),
+        params.nms_mode,
+        None,
+    ))
+}
+
+/// Uploads `img` into an input texture for `apply_grayscale` and `apply_canny`.
+///
+/// 8 bit images become `Rgba8Unorm` textures. Images with more precision keep it: gray images
+/// become `R32Float` textures and color images `Rgba32Float` textures.
+pub fn create_image_texture(renderer: &dyn Renderer, img: &image::DynamicImage) -> wgpu::Texture {
+    let color = img.color();
+    if color.bytes_per_pixel() / color.channel_count() == 1 {
+        create_rgba_texture(renderer, &img.to_rgba8())
+    } else if color.has_color() {
+        create_rgba_f32_texture(renderer, &img.to_rgba32f())
+    } else {
+        create_gray_texture(renderer, &img.to_luma32f())
+    }
+}
+
+/// Uploads `img` into a `Rgba8Unorm` texture, the input format of the gray scaling.
+pub fn create_rgba_texture(renderer: &dyn Renderer, img: &image::RgbaImage) -> wgpu::Texture {
+    let device = renderer.device();
+    let queue = renderer.queue();
+
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Input texture"),
+        size: wgpu::Extent3d {
+            width: img.width(),
+            height: img.height(),
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: wgpu::TextureFormat::Rgba8Unorm,
+        usage: wgpu::TextureUsages::COPY_DST | wgpu::TextureUsages::TEXTURE_BINDING,
+        view_formats: &[],
+    });
+
+    queue.write_texture(
+        wgpu::TexelCopyTextureInfo {
+            texture: &texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d::ZERO,
+            aspect: wgpu::TextureAspect::All,
+        },
+        img.as_raw(),
+        wgpu::TexelCopyBufferLayout {
+            offset: 0,
+            bytes_per_row: Some(std::mem::size_of::<[u8; 4]>() as u32 * img.width()),
+            rows_per_image: Some(img.height()),
+        },
+        texture.size(),
+    );
+
+    texture
+}
+
+/// Uploads `img` into a `R32Float` texture, the format of the textures between the stages.
+pub fn create_gray_texture(renderer: &dyn Renderer, img: &cpu::GrayF32Image) -> wgpu::Texture {
+    let device = renderer.device();
+    let queue = renderer.queue();
+
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Gray input texture"),
+        size: wgpu::Extent3d {
+            width: img.width(),
+            height: img.height(),
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: wgpu::TextureFormat::R32Float,
+        usage: wgpu::TextureUsages::COPY_DST
+            | wgpu::TextureUsages::COPY_SRC
+            | wgpu::TextureUsages::TEXTURE_BINDING
+            | wgpu::TextureUsages::STORAGE_BINDING,
+        view_formats: &[],
+    });
+
+    queue.write_texture(
+        wgpu::TexelCopyTextureInfo {
+            texture: &texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d::ZERO,
+            aspect: wgpu::TextureAspect::All,
+        },
+        bytemuck::cast_slice(img.as_raw()),
+        wgpu::TexelCopyBufferLayout {
+            offset: 0,
+            bytes_per_row: Some(std::mem::size_of::<f32>() as u32 * img.width()),
+            rows_per_image: Some(img.height()),
+        },
+        texture.size(),
+    );
+
+    texture
+}
+
+/// Uploads `img` into a `Rgba32Float` texture.
+pub fn create_rgba_f32_texture(
+    renderer: &dyn Renderer,
+    img: &image::Rgba32FImage,
+) -> wgpu::Texture {
+    let device = renderer.device();
+    let queue = renderer.queue();
+
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Float input texture"),
+        size: wgpu::Extent3d {
+            width: img.width(),
+            height: img.height(),
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: wgpu::TextureFormat::Rgba32Float,
+        usage: wgpu::TextureUsages::COPY_DST | wgpu::TextureUsages::TEXTURE_BINDING,
+        view_formats: &[],
+    });
+
+    queue.write_texture(
+        wgpu::TexelCopyTextureInfo {
+            texture: &texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d::ZERO,
+            aspect: wgpu::TextureAspect::All,
+        },
+        bytemuck::cast_slice(img.as_raw()),
+        wgpu::TexelCopyBufferLayout {
+            offset: 0,
+            bytes_per_row: Some(std::mem::size_of::<[f32; 4]>() as u32 * img.width()),
+            rows_per_image: Some(img.height()),
+        },
+        texture.size(),
+    );
+
+    texture
+}
+
+/// Reads the values of a `R32Float` texture back, row by row.
+pub fn read_texture(renderer: &dyn Renderer, texture: &wgpu::Texture) -> Vec<f32> {
+    if texture.format() != wgpu::TextureFormat::R32Float {
+        panic!("Texture has format: '{:?}'", texture.format());
+    }
+
+    let (buffer, padded_bytes_per_row) = copy_texture_to_buffer(renderer, texture);
+    let bytes = map_buffer(renderer, &buffer);
+
+    remove_row_padding(&bytes, texture.width(), padded_bytes_per_row)
+}
+
+/// Copies a texture with 4 bytes per pixel (like `R32Float`) into a mappable buffer whose rows are padded to the copy alignment.
+pub(crate) fn copy_texture_to_buffer(
+    renderer: &dyn Renderer,
+    texture: &wgpu::Texture,
+) -> (wgpu::Buffer, u32) {
+    let device = renderer.device();
+    let queue = renderer.queue();
+
+    let size = texture.size();
+    let unpadded_bytes_per_row = std::mem::size_of::<f32>() as u32 * size.width;
+    let padded_bytes_per_row = unpadded_bytes_per_row.next_multiple_of(256);
+
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Read texture: Output buffer"),
+        size: (padded_bytes_per_row * size.height) as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Read texture: Command encoder"),
+    });
+
+    encoder.copy_texture_to_buffer(
+        wgpu::TexelCopyTextureInfo {
+            texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d::ZERO,
+            aspect: wgpu::TextureAspect::All,
+        },
+        wgpu::TexelCopyBufferInfo {
+            buffer: &buffer,
+            layout: wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(padded_bytes_per_row),
+                rows_per_image: Some(size.height),
+            },
+        },
+        size,
+    );
+
+    queue.submit(std::iter::once(encoder.finish()));
+
+    (buffer, padded_bytes_per_row)
+}
+
+pub(crate) fn remove_row_padding(bytes: &[u8], width: u32, padded_bytes_per_row: u32) -> Vec<f32> {
+    let r32floats: &[f32] = bytemuck::cast_slice(bytes);
+
+    let floats_per_row = width as usize;
+    let padded_floats_per_row = (padded_bytes_per_row / std::mem::size_of::<f32>() as u32) as usize;
+
+    let mut values = Vec::with_capacity(floats_per_row * (r32floats.len() / padded_floats_per_row));
+    for row in r32floats.chunks(padded_floats_per_row) {
+        // take only the real pixels, skip padded floats at end of row
+        values.extend_from_slice(&row[..floats_per_row]);
+    }
+
+    values
+}
+
+/// Reads a `R32Float` texture back as an 8 bit gray image, gamma encoded like the binary saves
+/// the stages.
+pub fn read_luma_image(renderer: &dyn Renderer, texture: &wgpu::Texture) -> image::GrayImage {
+    let luma = read_texture(renderer, texture)
+        .into_iter()
+        .map(|value| (value.powf(1. / 2.2) * 255.) as u8)
+        .collect();
+
+    image::GrayImage::from_raw(texture.width(), texture.height(), luma).unwrap()
+}
+
+/// Reads a `Rgba8Unorm` texture back, for example the output of `apply_direction_visualization`.
+pub fn read_rgba_image(renderer: &dyn Renderer, texture: &wgpu::Texture) -> image::RgbaImage {
+    if texture.format() != wgpu::TextureFormat::Rgba8Unorm {
+        panic!("Texture has format: '{:?}'", texture.format());
+    }
+
+    // a pixel has 4 bytes like a `R32Float` pixel, so the copy is the same
+    let (buffer, padded_bytes_per_row) = copy_texture_to_buffer(renderer, texture);
+    let bytes = map_buffer(renderer, &buffer);
+    let pixels = remove_row_padding(&bytes, texture.width(), padded_bytes_per_row);
+
+    image::RgbaImage::from_raw(
+        texture.width(),
+        texture.height(),
+        bytemuck::cast_slice(&pixels).to_vec(),
+    )
+    .unwrap()
+}
+
+thread_local! {
+    /// Set while an async function encodes its work, see `forbid_blocking`.
+    static FORBIDS_BLOCKING: std::cell::Cell<bool> = const { std::cell::Cell::new(false) };
+}
+
+/// Runs `f`, which mustn't wait for the GPU since that would block the executor of the async
+/// function which calls it. Checked by the readbacks in debug builds.
+pub(crate) fn forbid_blocking<T>(f: impl FnOnce() -> T) -> T {
+    let forbade_blocking = FORBIDS_BLOCKING.replace(true);
+    let result = f();
+    FORBIDS_BLOCKING.set(forbade_blocking);
+
+    result
+}
+
+pub(crate) fn read_buffer(renderer: &dyn Renderer, buffer: &wgpu::Buffer) -> Vec<u8> {
+    let device = renderer.device();
+    let queue = renderer.queue();
+
+    let staging_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Read buffer: Staging buffer"),
+        size: buffer.size(),
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Read buffer: Command encoder"),
+    });
+    encoder.copy_buffer_to_buffer(buffer, 0, &staging_buffer, 0, buffer.size());
+    queue.submit(std::iter::once(encoder.finish()));
+
+    map_buffer(renderer, &staging_buffer)
+}
+
+fn map_buffer(renderer: &dyn Renderer, buffer: &wgpu::Buffer) -> Vec<u8> {
+    let slice = buffer.slice(..);
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    slice.map_async(wgpu::MapMode::Read, move |result| tx.send(result).unwrap());
+    debug_assert!(
+        !FORBIDS_BLOCKING.get(),
+        "Waited for the GPU while an async function encoded its work"
+    );
+    renderer.device().poll(wgpu::PollType::Wait).unwrap();
+    rx.recv().unwrap().unwrap();
+
+    let bytes = slice.get_mapped_range().to_vec();
+    buffer.unmap();
+
+    bytes
+}
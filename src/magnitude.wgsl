@@ -10,11 +10,19 @@ var magnitude: texture_storage_2d<r32float, write>;
 @group(0) @binding(3)
 var radian: texture_storage_2d<r32float, write>;
 
+struct Roi {
+    origin: vec2u,
+    size: vec2u,
+};
+
+@group(0) @binding(4)
+var<uniform> roi: Roi;
+
 @compute
 @workgroup_size(16, 16, 1)
 fn main(@builtin(global_invocation_id) gid: vec3u) {
-    let v = textureLoad(vertical, gid.xy, 0).r;
-    let h = textureLoad(horizontal, gid.xy, 0).r;
+    let v = textureLoad(vertical, gid.xy + roi.origin, 0).r;
+    let h = textureLoad(horizontal, gid.xy + roi.origin, 0).r;
 
     let mag = sqrt(v*v + h*h);
     textureStore(magnitude, gid.xy, vec4f(mag, 0., 0., 1.));
@@ -1,247 +1,558 @@
-use image::{ImageBuffer, ImageReader, Luma};
+mod batch;
+mod cli;
+mod sweep;
+
+use clap::Parser;
+use cli::{AdapterArgs, Cli, Command, DebugOutputArgs, MetricsArgs, OutputArgs, Stage};
+use image::{DynamicImage, GrayImage, ImageReader, RgbaImage};
 use pollster::FutureExt;
-use std::path::Path;
+use std::{cell::RefCell, path::Path, time::Duration};
 use wgpu_canny_edge_detection::{
-    Renderer as RendererTrait, apply_double_thresholding, apply_edge_tracking,
-    apply_gaussian_filter, apply_grayscale, apply_magnitude_and_angle,
-    apply_non_maximum_suppression, apply_sobel_operators,
+    CannyError, CannyParams, EdgeMasks, OutputEncoding, OverlayParams, Pipelines, Profile,
+    Profiler, Roi, Timer, apply_canny_tiled, apply_direction_visualization,
+    apply_double_thresholding, apply_edge_tracking, apply_gaussian_filter, apply_grayscale,
+    apply_magnitude_and_angle, apply_non_maximum_suppression, apply_output_encoding, apply_overlay,
+    apply_sobel_operators, cpu, create_image_texture, direction_legend, read_rgba_image,
+    read_texture,
+    sheet::{self, LabelledImage},
 };
 
+/// Width and height of the colour wheel which explains the direction colours.
+const DIRECTION_LEGEND_SIZE: u32 = 256;
+
+/// Wider stages are shrunk to this width in the sheet.
+const SHEET_CELL_WIDTH: u32 = 480;
+/// How long each stage is shown in an animated sheet.
+const SHEET_FRAME_DELAY: Duration = Duration::from_secs(1);
+
 struct Renderer {
     device: wgpu::Device,
     queue: wgpu::Queue,
+    pipelines: Pipelines,
+    profiler: Option<Profiler>,
 }
 
 impl Renderer {
-    pub fn new() -> Self {
-        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
-            backends: wgpu::Backends::VULKAN | wgpu::Backends::GL,
-            ..wgpu::InstanceDescriptor::from_env_or_default()
-        });
-
-        let adapter = instance
-            .request_adapter(&wgpu::RequestAdapterOptionsBase {
-                power_preference: wgpu::PowerPreference::HighPerformance,
+    pub fn new(args: &AdapterArgs) -> Result<Self, String> {
+        let instance = create_instance(args);
+
+        let adapter = match &args.adapter {
+            Some(selection) => instance
+                .enumerate_adapters(args.backends())
+                .into_iter()
+                .enumerate()
+                .find(|(index, adapter)| selection.matches(*index, &adapter.get_info()))
+                .map(|(_, adapter)| adapter)
+                .ok_or_else(|| format!("No adapter matches '{selection}', see `canny adapters`"))?,
+            None => instance
+                .request_adapter(&wgpu::RequestAdapterOptionsBase {
+                    power_preference: args.power_preference.into(),
+                    force_fallback_adapter: args.fallback_adapter,
+                    ..Default::default()
+                })
+                .block_on()
+                .map_err(|err| format!("Get adapter: {err}"))?,
+        };
+
+        // timestamp queries are only used for profiling, but they have to be enabled up front
+        let (device, queue) = adapter
+            .request_device(&wgpu::DeviceDescriptor {
+                required_features: adapter.features() & wgpu::Features::TIMESTAMP_QUERY,
+                // bigger images fit into a single texture
+                required_limits: adapter.limits(),
                 ..Default::default()
             })
             .block_on()
-            .expect("Get adapter");
-
-        let (device, queue) = adapter
-            .request_device(&wgpu::DeviceDescriptor::default())
-            .block_on()
-            .unwrap();
-
-        Self { device, queue }
+            .map_err(|err| format!("Get device: {err}"))?;
+
+        Ok(Self {
+            device,
+            queue,
+            pipelines: Pipelines::default(),
+            profiler: None,
+        })
     }
 
-    pub fn save_texture<P: AsRef<Path>>(&self, path: P, texture: &wgpu::Texture) {
+    pub fn save_texture<P: AsRef<Path>>(
+        &self,
+        path: P,
+        texture: &wgpu::Texture,
+        output: &OutputArgs,
+    ) {
         print!("Saving texture...");
-        if texture.format() != wgpu::TextureFormat::R32Float {
-            panic!("Texture has format: '{:?}'", texture.format());
-        }
-
-        let device = self.device();
-        let queue = self.queue();
-
-        let size = texture.size();
-        let unpadded_bytes_per_row = std::mem::size_of::<f32>() as u32 * size.width;
-        let padded_bytes_per_row = unpadded_bytes_per_row.next_multiple_of(256);
-
-        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Output buffer"),
-            size: (padded_bytes_per_row * size.height) as wgpu::BufferAddress,
-            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
-            mapped_at_creation: false,
-        });
-
-        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
-            label: Some("Saving command encoder"),
-        });
+        let values = self.read_encoded(texture, &output.encoding());
+        output
+            .save(path.as_ref(), values, texture.width(), texture.height())
+            .unwrap();
+        println!("DONE");
+    }
 
-        encoder.copy_texture_to_buffer(
-            wgpu::TexelCopyTextureInfo {
-                texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-                aspect: wgpu::TextureAspect::All,
-            },
-            wgpu::TexelCopyBufferInfo {
-                buffer: &buffer,
-                layout: wgpu::TexelCopyBufferLayout {
-                    offset: 0,
-                    bytes_per_row: Some(padded_bytes_per_row),
-                    rows_per_image: Some(size.height),
-                },
-            },
-            size,
+    /// Encodes a `R32Float` texture on the GPU and reads the values back.
+    fn read_encoded(&self, texture: &wgpu::Texture, encoding: &OutputEncoding) -> Vec<f32> {
+        let encoded = apply_output_encoding(
+            self,
+            texture.create_view(&wgpu::TextureViewDescriptor::default()),
+            encoding,
         );
+        read_texture(self, &encoded)
+    }
+
+    /// Whether the image is too big for a single texture and has to be processed in tiles.
+    fn needs_tiles(&self, img: &DynamicImage) -> bool {
+        let max_texture_dimension = self.device.limits().max_texture_dimension_2d;
+        img.width() > max_texture_dimension || img.height() > max_texture_dimension
+    }
 
-        queue.submit(std::iter::once(encoder.finish()));
+    /// Applies the edge detection to an image which is too big for a single texture and encodes the
+    /// edges, see `apply_canny_tiled`.
+    ///
+    /// The edges don't fit into a texture either, so they're encoded on the CPU.
+    fn detect_tiled(
+        &self,
+        img: &DynamicImage,
+        params: &CannyParams,
+        encoding: &OutputEncoding,
+    ) -> Result<Vec<f32>, CannyError> {
+        let max_texture_dimension = self.device.limits().max_texture_dimension_2d;
+        let edges = apply_canny_tiled(self, img, params, max_texture_dimension)?;
+        let edges = DynamicImage::ImageLuma8(edges).to_luma32f();
+
+        Ok(cpu::apply_output_encoding(&edges, encoding).into_raw())
+    }
 
-        {
-            let slice = buffer.slice(..);
+    /// Saves a `Rgba8Unorm` texture as an RGB image.
+    pub fn save_rgba_texture<P: AsRef<Path>>(&self, path: P, texture: &wgpu::Texture) {
+        print!("Saving texture...");
+        let img = DynamicImage::ImageRgba8(read_rgba_image(self, texture));
+        img.to_rgb8().save(path).unwrap();
+        println!("DONE");
+    }
+}
 
-            let (tx, rx) = std::sync::mpsc::channel();
-            slice.map_async(wgpu::MapMode::Read, move |result| tx.send(result).unwrap());
-            device.poll(wgpu::PollType::Wait).unwrap();
-            rx.recv().unwrap().unwrap();
+impl wgpu_canny_edge_detection::Renderer for Renderer {
+    fn device(&self) -> &wgpu::Device {
+        &self.device
+    }
 
-            // now buffer is mapped
-            let range = slice.get_mapped_range();
-            let r32floats: &[f32] = bytemuck::cast_slice(&range);
+    fn queue(&self) -> &wgpu::Queue {
+        &self.queue
+    }
 
-            let bytes_per_f32 = std::mem::size_of::<f32>() as u32;
-            let floats_per_row = (unpadded_bytes_per_row / bytes_per_f32) as usize;
-            let padded_floats_per_row = (padded_bytes_per_row / bytes_per_f32) as usize;
+    fn pipelines(&self) -> Option<&Pipelines> {
+        Some(&self.pipelines)
+    }
 
-            let mut luma_data: Vec<u8> =
-                Vec::with_capacity((floats_per_row * size.height as usize) as usize);
+    fn profiler(&self) -> Option<&Profiler> {
+        self.profiler.as_ref()
+    }
+}
 
-            for row in r32floats.chunks(padded_floats_per_row) {
-                // take only the real pixels, skip padded floats at end of row
-                let r32floats = &row[..floats_per_row];
-                for r32float in r32floats {
-                    let luma = r32float.powf(1. / 2.2) * 255.;
-                    luma_data.push(luma as u8);
-                }
+fn main() {
+    let cli = Cli::parse();
+
+    match cli.command {
+        Command::Detect {
+            input,
+            output_dir,
+            stages,
+            profile,
+            canny,
+            output,
+            debug,
+        } => {
+            check_output_args(&output);
+
+            let mut renderer = create_renderer(&cli.adapter);
+            if profile {
+                renderer.profiler = Some(Profiler::new(&renderer.device));
             }
 
-            let image_buffer: ImageBuffer<Luma<u8>, Vec<u8>> =
-                ImageBuffer::from_raw(size.width, size.height, luma_data).unwrap();
+            if let Err(err) = detect(
+                &renderer,
+                &input,
+                &output_dir,
+                &stages,
+                &CannyParams::from(&canny),
+                &output,
+                &debug,
+            ) {
+                eprintln!("{err}");
+                std::process::exit(1);
+            }
 
-            image_buffer.save(path).unwrap();
+            if let Some(profiler) = &renderer.profiler {
+                print_profile(&profiler.finish(&renderer));
+            }
         }
+        Command::Batch {
+            input_dir,
+            output_dir,
+            glob,
+            canny,
+            output,
+        } => {
+            check_output_args(&output);
+
+            batch::run(
+                &create_renderer(&cli.adapter),
+                &input_dir,
+                &output_dir,
+                &glob,
+                &CannyParams::from(&canny),
+                output,
+            )
+        }
+        Command::Compare { input, canny } => compare(
+            &create_renderer(&cli.adapter),
+            &input,
+            &CannyParams::from(&canny),
+        ),
+        Command::Eval {
+            predicted,
+            ground_truth,
+            metrics,
+        } => eval(&predicted, &ground_truth, &metrics),
+        Command::Sweep(args) => sweep::run(&create_renderer(&cli.adapter), &args),
+        Command::Adapters => list_adapters(&cli.adapter),
+    }
+}
 
-        println!("DONE");
-        buffer.unmap();
-    }
-
-    fn load_rgba_image(&self, in_img: image::DynamicImage) -> wgpu::Texture {
-        print!("Loading texture... ");
-
-        let device = self.device();
-        let queue = self.queue();
-
-        let img = in_img.to_rgba8();
-
-        let texture = device.create_texture(&wgpu::TextureDescriptor {
-            label: Some("Input texture"),
-            size: wgpu::Extent3d {
-                width: img.width(),
-                height: img.height(),
-                depth_or_array_layers: 1,
-            },
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Rgba8Unorm,
-            usage: wgpu::TextureUsages::COPY_DST | wgpu::TextureUsages::TEXTURE_BINDING,
-            view_formats: &[],
-        });
-
-        queue.write_texture(
-            wgpu::TexelCopyTextureInfo {
-                texture: &texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-                aspect: wgpu::TextureAspect::All,
-            },
-            &img.as_raw(),
-            wgpu::TexelCopyBufferLayout {
-                offset: 0,
-                bytes_per_row: Some(std::mem::size_of::<[u8; 4]>() as u32 * img.width()),
-                rows_per_image: Some(img.height()),
-            },
-            texture.size(),
-        );
+fn create_renderer(args: &AdapterArgs) -> Renderer {
+    Renderer::new(args).unwrap_or_else(|err| {
+        eprintln!("{err}");
+        std::process::exit(1);
+    })
+}
 
-        println!("DONE");
-        texture
+fn check_output_args(args: &OutputArgs) {
+    if let Err(err) = args.validate() {
+        eprintln!("{err}");
+        std::process::exit(1);
     }
 }
 
-impl wgpu_canny_edge_detection::Renderer for Renderer {
-    fn device(&self) -> &wgpu::Device {
-        &self.device
+fn create_instance(args: &AdapterArgs) -> wgpu::Instance {
+    wgpu::Instance::new(&wgpu::InstanceDescriptor {
+        backends: args.backends(),
+        ..wgpu::InstanceDescriptor::from_env_or_default()
+    })
+}
+
+fn list_adapters(args: &AdapterArgs) {
+    let adapters = create_instance(args).enumerate_adapters(args.backends());
+    if adapters.is_empty() {
+        println!("No adapters found");
+        return;
     }
 
-    fn queue(&self) -> &wgpu::Queue {
-        &self.queue
+    for (index, adapter) in adapters.iter().enumerate() {
+        let info = adapter.get_info();
+        println!(
+            "{index}: {} ({:?}, {:?}, driver: {} {})",
+            info.name, info.backend, info.device_type, info.driver, info.driver_info
+        );
     }
 }
 
-fn main() {
-    let renderer = Renderer::new();
+fn detect(
+    renderer: &Renderer,
+    input_file: &Path,
+    output_dir: &Path,
+    stages: &[Stage],
+    params: &CannyParams,
+    output: &OutputArgs,
+    debug: &DebugOutputArgs,
+) -> Result<(), CannyError> {
+    // every numbered stage ends up in the sheet
+    let sheet_images = RefCell::new(Vec::new());
+
+    // only the selected stages are read back from the GPU
+    let save_stage = |stage: Stage, name: &str, texture: &wgpu::Texture| {
+        if stage.is_selected(stages) {
+            renderer.save_texture(
+                output_dir.join(format!("{name}.{}", output.format.extension())),
+                texture,
+                output,
+            );
+        }
+        if debug.sheet.is_some() {
+            let values = renderer.read_encoded(texture, &output.sheet_encoding());
+            let gray = GrayImage::from_raw(
+                texture.width(),
+                texture.height(),
+                values
+                    .into_iter()
+                    .map(|value| (value.clamp(0., 1.) * u8::MAX as f32).round() as u8)
+                    .collect(),
+            )
+            .unwrap();
+            sheet_images
+                .borrow_mut()
+                .push(sheet_image(name, DynamicImage::ImageLuma8(gray).to_rgba8()));
+        }
+    };
+    let last_stage = match debug.sheet {
+        Some(_) => Stage::last(stages).max(Stage::Final),
+        None => Stage::last(stages),
+    };
 
-    let mut args = std::env::args();
+    let input = ImageReader::open(input_file).unwrap().decode().unwrap();
 
-    // skip binary path
-    args.next();
+    if renderer.needs_tiles(&input) {
+        println!(
+            "The image doesn't fit into a texture, only its final edges are computed in tiles"
+        );
+        let edges = renderer.detect_tiled(&input, params, &output.encoding())?;
 
-    let input_file = args.next().expect("Input file path is added");
-    let output_dir = args.next().expect("Output dir path is added");
+        print!("Saving texture...");
+        let path = output_dir.join(format!("7_edge_tracking.{}", output.format.extension()));
+        output
+            .save(&path, edges, input.width(), input.height())
+            .unwrap();
+        println!("DONE");
 
-    let input = ImageReader::open(input_file).unwrap().decode().unwrap();
+        return Ok(());
+    }
 
-    let input_texture = renderer.load_rgba_image(input);
+    print!("Loading texture... ");
+    let input_texture = create_image_texture(renderer, &input);
+    println!("DONE");
 
     // 1. gray scaling
     let gray_scale = apply_grayscale(
-        &renderer,
+        renderer,
         input_texture.create_view(&wgpu::TextureViewDescriptor::default()),
-    );
-    renderer.save_texture(format!("{output_dir}/1_gray_scale.png"), &gray_scale);
+        None,
+    )?;
+    save_stage(Stage::Gray, "1_gray_scale", &gray_scale);
+    if last_stage == Stage::Gray {
+        return Ok(());
+    }
 
     // 2. Remove noise with gaussian filtering
     let gaussian = apply_gaussian_filter(
-        &renderer,
+        renderer,
         gray_scale.create_view(&wgpu::TextureViewDescriptor::default()),
+        params.sigma,
+        params.kernel_radius,
+        None,
     );
-    renderer.save_texture(format!("{output_dir}/2_gaussian.png"), &gaussian);
+    save_stage(Stage::Gaussian, "2_gaussian", &gaussian);
+    if last_stage == Stage::Gaussian {
+        return Ok(());
+    }
 
     // 3.1 Detect horizontal and vertical edges
     let (horizontal, vertical) = apply_sobel_operators(
-        &renderer,
+        renderer,
         gaussian.create_view(&wgpu::TextureViewDescriptor::default()),
+        params.gradient_operator,
+        None,
     );
-    renderer.save_texture(format!("{output_dir}/3_horizontal.png"), &horizontal);
-    renderer.save_texture(format!("{output_dir}/3_vertical.png"), &vertical);
+    save_stage(Stage::Gradients, "3_horizontal", &horizontal);
+    save_stage(Stage::Gradients, "3_vertical", &vertical);
+    if last_stage == Stage::Gradients {
+        return Ok(());
+    }
 
     // 3.2 compute gradient magnitude
     let (magnitudes, radians) = apply_magnitude_and_angle(
-        &renderer,
+        renderer,
         vertical.create_view(&wgpu::TextureViewDescriptor::default()),
         horizontal.create_view(&wgpu::TextureViewDescriptor::default()),
+        None,
     );
-    renderer.save_texture(format!("{output_dir}/4_magnitude.png"), &magnitudes);
-    renderer.save_texture(format!("{output_dir}/4_radians.png"), &radians);
+    save_stage(Stage::Magnitude, "4_magnitude", &magnitudes);
+    save_stage(Stage::Magnitude, "4_radians", &radians);
+
+    if Stage::Direction.is_selected(stages) || debug.sheet.is_some() {
+        let direction = apply_direction_visualization(
+            renderer,
+            magnitudes.create_view(&wgpu::TextureViewDescriptor::default()),
+            radians.create_view(&wgpu::TextureViewDescriptor::default()),
+        );
+        let direction = read_rgba_image(renderer, &direction);
+
+        if Stage::Direction.is_selected(stages) {
+            print!("Saving texture...");
+            DynamicImage::ImageRgba8(direction.clone())
+                .to_rgb8()
+                .save(output_dir.join("4_direction.png"))
+                .unwrap();
+            direction_legend(DIRECTION_LEGEND_SIZE)
+                .save(output_dir.join("4_direction_legend.png"))
+                .unwrap();
+            println!("DONE");
+        }
+        if debug.sheet.is_some() {
+            sheet_images
+                .borrow_mut()
+                .push(sheet_image("4_direction", direction));
+        }
+    }
+    if matches!(last_stage, Stage::Magnitude | Stage::Direction) {
+        return Ok(());
+    }
 
     // 4. apply non maximum suppression
     let non_maximum_suppression = apply_non_maximum_suppression(
-        &renderer,
+        renderer,
         magnitudes.create_view(&wgpu::TextureViewDescriptor::default()),
         radians.create_view(&wgpu::TextureViewDescriptor::default()),
+        params.nms_mode,
+        None,
     );
-    renderer.save_texture(
-        format!("{output_dir}/5_non_maximum_suppression.png"),
+    save_stage(
+        Stage::Nms,
+        "5_non_maximum_suppression",
         &non_maximum_suppression,
     );
+    if last_stage == Stage::Nms {
+        return Ok(());
+    }
 
     // 5. Apply upper and lower thresholds
     let threshold_texture = apply_double_thresholding(
-        &renderer,
+        renderer,
         non_maximum_suppression.create_view(&wgpu::TextureViewDescriptor::default()),
+        params.thresholds,
+        None,
+        &EdgeMasks::default(),
+        None,
     );
-    renderer.save_texture(
-        format!("{output_dir}/6_threshold_texture.png"),
-        &threshold_texture,
-    );
+    save_stage(Stage::Threshold, "6_threshold_texture", &threshold_texture);
+    if last_stage == Stage::Threshold {
+        return Ok(());
+    }
 
     // 6. edge tracking
+    // works in place without a ROI, but the overlay needs the thresholds to tell weak edges apart
+    let whole_image = Roi::new(0, 0, threshold_texture.width(), threshold_texture.height());
     let edge_tracking = apply_edge_tracking(
-        &renderer,
+        renderer,
         threshold_texture.create_view(&wgpu::TextureViewDescriptor::default()),
+        Stage::Overlay.is_selected(stages).then_some(whole_image),
     );
-    renderer.save_texture(format!("{output_dir}/7_edge_tracking.png"), &edge_tracking);
+    save_stage(Stage::Final, "7_edge_tracking", &edge_tracking);
+
+    if let Some(path) = &debug.sheet {
+        save_sheet(path, &sheet_images.into_inner(), debug.sheet_columns);
+    }
+    if last_stage == Stage::Final {
+        return Ok(());
+    }
+
+    // the edges over the input image
+    let overlay = apply_overlay(
+        renderer,
+        input_texture.create_view(&wgpu::TextureViewDescriptor::default()),
+        threshold_texture.create_view(&wgpu::TextureViewDescriptor::default()),
+        edge_tracking.create_view(&wgpu::TextureViewDescriptor::default()),
+        &OverlayParams::from(debug),
+    )?;
+    renderer.save_rgba_texture(output_dir.join("overlay.png"), &overlay);
+
+    Ok(())
+}
+
+/// A stage of the sheet, labelled with its file name and shrunk to `SHEET_CELL_WIDTH`.
+fn sheet_image(name: &str, img: RgbaImage) -> LabelledImage {
+    let img = if img.width() > SHEET_CELL_WIDTH {
+        let height = (img.height() as u64 * SHEET_CELL_WIDTH as u64 / img.width() as u64).max(1);
+        image::imageops::resize(
+            &img,
+            SHEET_CELL_WIDTH,
+            height as u32,
+            image::imageops::FilterType::Triangle,
+        )
+    } else {
+        img
+    };
+
+    LabelledImage::new(name.replace('_', " "), img)
+}
+
+fn save_sheet(path: &Path, images: &[LabelledImage], columns: Option<u32>) {
+    print!("Saving sheet...");
+    let is_gif = path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("gif"));
+    if is_gif {
+        sheet::save_gif(path, images, SHEET_FRAME_DELAY).unwrap();
+    } else {
+        // about as many columns as rows
+        let columns = columns.unwrap_or((images.len() as f32).sqrt().ceil() as u32);
+        sheet::contact_sheet(images, columns).save(path).unwrap();
+    }
+    println!("DONE");
+}
+
+fn print_profile(profile: &Profile) {
+    match profile.timer {
+        Timer::GpuTimestamps => println!("GPU time per stage (timestamp queries):"),
+        Timer::CpuWallClock => {
+            println!("Wall clock time per stage (the adapter doesn't support timestamp queries):")
+        }
+    }
+
+    println!("{:<24}  {:>10}", "Stage", "Time (ms)");
+    for stage in &profile.stages {
+        println!(
+            "{:<24}  {:>10.3}",
+            stage.stage,
+            stage.duration.as_secs_f64() * 1000.
+        );
+    }
+    println!(
+        "{:<24}  {:>10.3}",
+        "total",
+        profile.total().as_secs_f64() * 1000.
+    );
+}
+
+fn compare(renderer: &Renderer, input_file: &Path, params: &CannyParams) {
+    let input = ImageReader::open(input_file).unwrap().decode().unwrap();
+    let errors = cpu::compare_with_gpu(renderer, &input.to_rgba8(), params);
+
+    println!(
+        "{:<24}  {:>14}  {:>14}",
+        "Stage", "Max abs error", "Mean abs error"
+    );
+    for error in errors {
+        println!(
+            "{:<24}  {:>14.3e}  {:>14.3e}",
+            error.stage, error.max_abs_error, error.mean_abs_error
+        );
+    }
+}
+
+fn eval(predicted: &Path, ground_truth: &Path, args: &MetricsArgs) {
+    let load = |path: &Path| {
+        ImageReader::open(path)
+            .and_then(|reader| reader.with_guessed_format())
+            .map_err(|err| err.to_string())
+            .and_then(|reader| reader.decode().map_err(|err| err.to_string()))
+            .unwrap_or_else(|err| {
+                eprintln!("Open {}: {err}", path.display());
+                std::process::exit(1);
+            })
+            .to_luma8()
+    };
+
+    let (predicted, ground_truth) = (load(predicted), load(ground_truth));
+    if predicted.dimensions() != ground_truth.dimensions() {
+        eprintln!(
+            "The edge map has a size of {:?}, but the ground truth of {:?}",
+            predicted.dimensions(),
+            ground_truth.dimensions()
+        );
+        std::process::exit(1);
+    }
+
+    let metrics = args.evaluate(&predicted, &ground_truth);
+    println!("Pratt's FOM  {:.4}", metrics.pratt_fom);
+    println!("Precision    {:.4}", metrics.precision);
+    println!("Recall       {:.4}", metrics.recall);
+    println!("F1           {:.4}", metrics.f1);
+    println!("Hausdorff    {:.2}px", metrics.hausdorff);
 }
@@ -4,12 +4,42 @@ var input: texture_2d<f32>;
 @group(0) @binding(1)
 var<storage, read_write> max_value: atomic<u32>;
 
+struct Roi {
+    origin: vec2u,
+    size: vec2u,
+};
+
+@group(0) @binding(2)
+var<uniform> roi: Roi;
+
+@group(0) @binding(3)
+var ignore_mask: texture_2d<f32>;
+
+struct Masks {
+    origin: vec2u,
+    use_ignore_mask: u32,
+    use_seed_mask: u32,
+};
+
+@group(0) @binding(4)
+var<uniform> masks: Masks;
+
 const u32_MAX: f32 = 4294967295.;
 
 @compute
 @workgroup_size(16, 16, 1)
 fn main(@builtin(global_invocation_id) gid: vec3u) {
-    let value = textureLoad(input, gid.xy, 0).r;
+    if (gid.x >= roi.size.x || gid.y >= roi.size.y) {
+        return;
+    }
+
+    // ignored pixels shouldn't influence the thresholds
+    let mask_coord = gid.xy + roi.origin + masks.origin;
+    if (masks.use_ignore_mask != 0u && textureLoad(ignore_mask, mask_coord, 0).r == 0.) {
+        return;
+    }
+
+    let value = textureLoad(input, gid.xy + roi.origin, 0).r;
 
     atomicMax(&max_value, u32(floor(value * u32_MAX)));
 }
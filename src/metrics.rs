@@ -0,0 +1,183 @@
+//! Quality metrics of edge maps compared to a ground truth.
+//!
+//! Every non-zero pixel of an edge map is an edge.
+use image::GrayImage;
+
+/// Scaling constant of Pratt's figure of merit, as proposed by Pratt.
+const PRATT_ALPHA: f32 = 1. / 9.;
+
+/// Marks pixels without an edge in the distance transform.
+const NO_EDGE: f64 = 1e20;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EdgeMetrics {
+    /// Pratt's figure of merit, `1` is a perfect match.
+    pub pratt_fom: f32,
+    /// Fraction of the detected edges which are at most `tolerance` away from a real edge.
+    pub precision: f32,
+    /// Fraction of the real edges which are at most `tolerance` away from a detected edge.
+    pub recall: f32,
+    /// Harmonic mean of the precision and the recall.
+    pub f1: f32,
+    /// Largest distance of an edge to the nearest edge of the other map, in pixels.
+    /// Infinite if only one of the maps has edges.
+    pub hausdorff: f32,
+}
+
+/// Compares the `detected` edges with the `ground_truth`. Both have to have the same size.
+///
+/// Without detected edges the precision is `1` and without real edges the recall is `1`.
+pub fn evaluate(detected: &GrayImage, ground_truth: &GrayImage, tolerance: f32) -> EdgeMetrics {
+    assert_eq!(
+        detected.dimensions(),
+        ground_truth.dimensions(),
+        "The edge maps have different sizes"
+    );
+
+    let distances_to_ground_truth = distance_transform(ground_truth);
+    let distances_to_detected = distance_transform(detected);
+
+    let edge_distances = |edges: &GrayImage, distances: &[f32]| -> Vec<f32> {
+        edges
+            .as_raw()
+            .iter()
+            .zip(distances)
+            .filter(|(value, _)| **value > 0)
+            .map(|(_, distance)| *distance)
+            .collect()
+    };
+    let detected_distances = edge_distances(detected, &distances_to_ground_truth);
+    let ground_truth_distances = edge_distances(ground_truth, &distances_to_detected);
+
+    let fraction_within_tolerance = |distances: &[f32]| {
+        if distances.is_empty() {
+            return 1.;
+        }
+
+        let within = distances.iter().filter(|&&d| d <= tolerance).count();
+        within as f32 / distances.len() as f32
+    };
+    let precision = fraction_within_tolerance(&detected_distances);
+    let recall = fraction_within_tolerance(&ground_truth_distances);
+    let f1 = if precision + recall > 0. {
+        2. * precision * recall / (precision + recall)
+    } else {
+        0.
+    };
+
+    let amount = detected_distances.len().max(ground_truth_distances.len());
+    let pratt_fom = if amount == 0 {
+        1.
+    } else {
+        detected_distances
+            .iter()
+            .map(|d| 1. / (1. + PRATT_ALPHA * d * d))
+            .sum::<f32>()
+            / amount as f32
+    };
+
+    let hausdorff = match (
+        detected_distances.is_empty(),
+        ground_truth_distances.is_empty(),
+    ) {
+        (true, true) => 0.,
+        (true, false) | (false, true) => f32::INFINITY,
+        (false, false) => detected_distances
+            .iter()
+            .chain(ground_truth_distances.iter())
+            .copied()
+            .fold(0., f32::max),
+    };
+
+    EdgeMetrics {
+        pratt_fom,
+        precision,
+        recall,
+        f1,
+        hausdorff,
+    }
+}
+
+/// Returns the euclidean distance of each pixel to the nearest edge, row by row.
+///
+/// Uses the separable algorithm of Felzenszwalb and Huttenlocher, first over the columns and then
+/// over the rows.
+fn distance_transform(edges: &GrayImage) -> Vec<f32> {
+    let (width, height) = (edges.width() as usize, edges.height() as usize);
+
+    // f64, because the squared positions of big images don't fit into the mantissa of f32
+    let mut squared: Vec<f64> = edges
+        .as_raw()
+        .iter()
+        .map(|&value| if value > 0 { 0. } else { NO_EDGE })
+        .collect();
+
+    let mut line = Vec::with_capacity(width.max(height));
+    for x in 0..width {
+        line.clear();
+        line.extend((0..height).map(|y| squared[x + y * width]));
+        for (y, value) in squared_distance_1d(&line).into_iter().enumerate() {
+            squared[x + y * width] = value;
+        }
+    }
+    for row in squared.chunks_mut(width.max(1)) {
+        let transformed = squared_distance_1d(row);
+        row.copy_from_slice(&transformed);
+    }
+
+    squared
+        .into_iter()
+        .map(|value| {
+            if value >= NO_EDGE {
+                f32::INFINITY
+            } else {
+                value.sqrt() as f32
+            }
+        })
+        .collect()
+}
+
+/// Lower envelope of the parabolas rooted at each sample of `f`.
+fn squared_distance_1d(f: &[f64]) -> Vec<f64> {
+    let n = f.len();
+    let mut distances = vec![0.; n];
+    if n == 0 {
+        return distances;
+    }
+
+    // positions of the parabolas of the envelope and the borders between them
+    let mut vertices = vec![0usize; n];
+    let mut borders = vec![0f64; n + 1];
+    let mut k = 0;
+    borders[0] = f64::NEG_INFINITY;
+    borders[1] = f64::INFINITY;
+
+    let intersection = |q: usize, p: usize| {
+        ((f[q] + (q * q) as f64) - (f[p] + (p * p) as f64)) / (2. * q as f64 - 2. * p as f64)
+    };
+
+    for q in 1..n {
+        let mut s = intersection(q, vertices[k]);
+        while s <= borders[k] {
+            k -= 1;
+            s = intersection(q, vertices[k]);
+        }
+
+        k += 1;
+        vertices[k] = q;
+        borders[k] = s;
+        borders[k + 1] = f64::INFINITY;
+    }
+
+    k = 0;
+    for (q, distance) in distances.iter_mut().enumerate() {
+        while borders[k + 1] < q as f64 {
+            k += 1;
+        }
+
+        let offset = q as f64 - vertices[k] as f64;
+        *distance = offset * offset + f[vertices[k]];
+    }
+
+    distances
+}
@@ -0,0 +1,44 @@
+@group(0) @binding(0)
+var input: texture_2d<f32>;
+
+// the smallest and the biggest value, as `ordered_bits` of the floats
+@group(0) @binding(1)
+var<storage, read_write> min_max: array<atomic<u32>, 2>;
+
+struct Encoding {
+    transfer: u32,
+    normalize: u32,
+    absolute: u32,
+    invert: u32,
+};
+
+@group(0) @binding(2)
+var<uniform> encoding: Encoding;
+
+// maps the floats to integers with the same order, so negative values can be compared atomically
+fn ordered_bits(value: f32) -> u32 {
+    let bits = bitcast<u32>(value);
+    return select(bits | 0x80000000u, ~bits, (bits & 0x80000000u) != 0u);
+}
+
+@compute
+@workgroup_size(16, 16, 1)
+fn main(@builtin(global_invocation_id) gid: vec3u) {
+    let size = textureDimensions(input);
+    if (gid.x >= size.x || gid.y >= size.y) {
+        return;
+    }
+
+    var value = textureLoad(input, gid.xy, 0).r;
+    if encoding.absolute != 0u {
+        value = abs(value);
+    }
+
+    // NaN would be bigger than every value
+    if value != value {
+        return;
+    }
+
+    atomicMin(&min_max[0], ordered_bits(value));
+    atomicMax(&min_max[1], ordered_bits(value));
+}
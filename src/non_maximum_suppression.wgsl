@@ -7,26 +7,63 @@ var radians: texture_2d<f32>;
 @group(0) @binding(2)
 var output: texture_storage_2d<r32float, write>;
 
+struct Roi {
+    origin: vec2u,
+    size: vec2u,
+};
+
+@group(0) @binding(3)
+var<uniform> roi: Roi;
+
+const MODE_INTERPOLATED: u32 = 0;
+const MODE_QUANTIZED: u32 = 1;
+
+@group(0) @binding(4)
+var<uniform> mode: u32;
+
+const PI: f32 = 3.14159265358979;
+
 @compute
 @workgroup_size(16, 16, 1)
 fn main(@builtin(global_invocation_id) gid: vec3u) {
-    let magnitude = textureLoad(magnitudes, gid.xy, 0).r;
-    let radian = textureLoad(radians, gid.xy, 0).r;
+    let in_coord = gid.xy + roi.origin;
+    let magnitude = textureLoad(magnitudes, in_coord, 0).r;
+    let radian = textureLoad(radians, in_coord, 0).r;
+
+    var m1 = 0.;
+    var m2 = 0.;
+    if (mode == MODE_QUANTIZED) {
+        // round the direction to the nearest multiple of 45°
+        let quantized = round(radian / (PI / 4.)) * (PI / 4.);
+        let offset = vec2i(round(vec2f(cos(quantized), sin(quantized))));
 
-    let coord = vec2f(gid.xy);
-    let dir = vec2f(cos(radian), sin(radian));
+        m1 = neighbour(vec2i(in_coord) + offset);
+        m2 = neighbour(vec2i(in_coord) - offset);
+    } else {
+        let coord = vec2f(in_coord);
+        let dir = vec2f(cos(radian), sin(radian));
 
-    let p1 = coord + dir;
-    let p2 = coord - dir;
+        let p1 = coord + dir;
+        let p2 = coord - dir;
 
-    let m1 = bilinear_interpolation(p1);
-    let m2 = bilinear_interpolation(p2);
+        m1 = bilinear_interpolation(p1);
+        m2 = bilinear_interpolation(p2);
+    }
 
     if (m1 < magnitude && m2 < magnitude) {
         textureStore(output, gid.xy, vec4f(magnitude, 0., 0., 1.));
     }
 }
 
+fn neighbour(p: vec2i) -> f32 {
+    if (p.x >= 0 && p.y >= 0 && is_in_texture(vec2u(p))) {
+        return textureLoad(magnitudes, p, 0).r;
+    } else {
+        // basically early exit
+        return 1e10;
+    }
+}
+
 fn bilinear_interpolation(p: vec2f) -> f32 {
     let id = vec2u(floor(p));
 
@@ -56,7 +93,7 @@ fn bilinear_interpolation(p: vec2f) -> f32 {
 
 fn is_in_texture(pixel_coord: vec2u) -> bool {
     let p = vec2f(pixel_coord);
-    let sizeu: vec2u = textureDimensions(output);
+    let sizeu: vec2u = textureDimensions(magnitudes);
     let sizef: vec2f = vec2f(sizeu);
 
     let x_is_valid = p.x >= 0. && p.x < sizef.x;
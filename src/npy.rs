@@ -0,0 +1,64 @@
+//! Export of stage values as NumPy `.npy` files.
+//!
+//! The files contain a `float32` array with the shape `(height, width)`, which `numpy.load` reads
+//! without any conversion.
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+use crate::{Renderer, read_texture};
+
+const MAGIC: &[u8] = b"\x93NUMPY";
+
+/// The header of version 1.0 files is padded so the data starts at a multiple of this.
+const ALIGNMENT: usize = 64;
+
+/// Writes the values (row by row) of an image with the given size.
+pub fn write<W: Write>(mut writer: W, values: &[f32], width: u32, height: u32) -> io::Result<()> {
+    if values.len() != width as usize * height as usize {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "{} values don't make an image of {width}x{height} pixels",
+                values.len()
+            ),
+        ));
+    }
+
+    let mut header =
+        format!("{{'descr': '<f4', 'fortran_order': False, 'shape': ({height}, {width}), }}");
+    // magic, version, header length, header and the terminating newline
+    let unpadded_len = MAGIC.len() + 2 + 2 + header.len() + 1;
+    header.extend(std::iter::repeat_n(
+        ' ',
+        unpadded_len.next_multiple_of(ALIGNMENT) - unpadded_len,
+    ));
+    header.push('\n');
+
+    writer.write_all(MAGIC)?;
+    writer.write_all(&[1, 0])?;
+    writer.write_all(&(header.len() as u16).to_le_bytes())?;
+    writer.write_all(header.as_bytes())?;
+    for value in values {
+        writer.write_all(&value.to_le_bytes())?;
+    }
+
+    writer.flush()
+}
+
+/// Writes the values to a file.
+pub fn save(path: impl AsRef<Path>, values: &[f32], width: u32, height: u32) -> io::Result<()> {
+    write(BufWriter::new(File::create(path)?), values, width, height)
+}
+
+/// Reads a `R32Float` texture back and writes its values to a file.
+pub fn save_texture(
+    renderer: &dyn Renderer,
+    texture: &wgpu::Texture,
+    path: impl AsRef<Path>,
+) -> io::Result<()> {
+    let values = read_texture(renderer, texture);
+    save(path, &values, texture.width(), texture.height())
+}
@@ -0,0 +1,92 @@
+@group(0) @binding(0)
+var input: texture_2d<f32>;
+
+@group(0) @binding(1)
+var double_thresholding: texture_2d<f32>;
+
+@group(0) @binding(2)
+var edges: texture_2d<f32>;
+
+@group(0) @binding(3)
+var output: texture_storage_2d<rgba8unorm, write>;
+
+struct InputFormat {
+    single_channel: u32,
+    srgb: u32,
+};
+
+@group(0) @binding(4)
+var<uniform> input_format: InputFormat;
+
+struct Overlay {
+    strong_color: vec4f,
+    weak_color: vec4f,
+    dilation: u32,
+};
+
+@group(0) @binding(5)
+var<uniform> overlay: Overlay;
+
+const IS_EDGE: f32 = 1.0;
+
+const NO_EDGE: u32 = 0u;
+const WEAK_EDGE: u32 = 1u;
+const STRONG_EDGE: u32 = 2u;
+
+fn encode_srgb(linear: vec3f) -> vec3f {
+    let low = linear * 12.92;
+    let high = 1.055 * pow(linear, vec3f(1. / 2.4)) - 0.055;
+    return select(high, low, linear <= vec3f(0.0031308));
+}
+
+// the strongest edge within the dilation radius
+fn nearby_edge(coord: vec2i, size: vec2i) -> u32 {
+    let radius = i32(overlay.dilation);
+
+    var edge = NO_EDGE;
+    for (var y = -radius; y <= radius; y++) {
+        for (var x = -radius; x <= radius; x++) {
+            let neighbour = coord + vec2i(x, y);
+            if x * x + y * y > radius * radius || any(neighbour < vec2i(0)) || any(neighbour >= size) {
+                continue;
+            }
+
+            if textureLoad(edges, neighbour, 0).r != IS_EDGE {
+                continue;
+            }
+
+            // weak edges which were kept by the edge tracking aren't edges before it
+            if textureLoad(double_thresholding, neighbour, 0).r == IS_EDGE {
+                return STRONG_EDGE;
+            }
+            edge = WEAK_EDGE;
+        }
+    }
+
+    return edge;
+}
+
+@compute
+@workgroup_size(16, 16, 1)
+fn main(@builtin(global_invocation_id) gid: vec3u) {
+    let size = textureDimensions(edges);
+    if (gid.x >= size.x || gid.y >= size.y) {
+        return;
+    }
+
+    var pixel = textureLoad(input, gid.xy, 0).rgb;
+    if input_format.single_channel != 0u {
+        pixel = vec3f(pixel.r);
+    } else if input_format.srgb != 0u {
+        pixel = encode_srgb(pixel);
+    }
+
+    let edge = nearby_edge(vec2i(gid.xy), vec2i(size));
+    if edge == STRONG_EDGE {
+        pixel = mix(pixel, overlay.strong_color.rgb, overlay.strong_color.a);
+    } else if edge == WEAK_EDGE {
+        pixel = mix(pixel, overlay.weak_color.rgb, overlay.weak_color.a);
+    }
+
+    textureStore(output, gid.xy, vec4f(pixel, 1.));
+}
@@ -0,0 +1,138 @@
+/// The parameters of every step of the canny edge detection.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CannyParams {
+    /// Standard deviation of the gaussian filter.
+    pub sigma: f32,
+    /// The gaussian kernel has a size of `2 * kernel_radius + 1`.
+    pub kernel_radius: u32,
+    pub gradient_operator: GradientOperator,
+    pub nms_mode: NmsMode,
+    pub thresholds: Thresholds,
+}
+
+impl Default for CannyParams {
+    fn default() -> Self {
+        Self {
+            sigma: 1.6,
+            kernel_radius: 5,
+            gradient_operator: GradientOperator::default(),
+            nms_mode: NmsMode::default(),
+            thresholds: Thresholds::default(),
+        }
+    }
+}
+
+impl CannyParams {
+    /// Amount of pixels around a pixel which are read until (and including) the non maximum suppression.
+    pub fn apron(&self) -> u32 {
+        // gradient operators (1) + non maximum suppression (2)
+        self.kernel_radius + 1 + 2
+    }
+}
+
+/// The kernels which are used to compute the horizontal and vertical gradients.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GradientOperator {
+    #[default]
+    Sobel,
+    Scharr,
+    Prewitt,
+}
+
+impl GradientOperator {
+    /// Returns the 3x3 kernels (row major) for the vertical and horizontal gradients.
+    pub fn kernels(&self) -> ([f32; 9], [f32; 9]) {
+        match self {
+            Self::Sobel => (
+                [-1., -2., -1., 0., 0., 0., 1., 2., 1.],
+                [-1., 0., 1., -2., 0., 2., -1., 0., 1.],
+            ),
+            // scaled by 1/4 to have the same gain as the sobel kernels, otherwise the gradients
+            // saturate
+            Self::Scharr => (
+                [-0.75, -2.5, -0.75, 0., 0., 0., 0.75, 2.5, 0.75],
+                [-0.75, 0., 0.75, -2.5, 0., 2.5, -0.75, 0., 0.75],
+            ),
+            Self::Prewitt => (
+                [-1., -1., -1., 0., 0., 0., 1., 1., 1.],
+                [-1., 0., 1., -1., 0., 1., -1., 0., 1.],
+            ),
+        }
+    }
+}
+
+/// How the neighbours along the gradient are looked up in the non maximum suppression.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NmsMode {
+    /// Interpolate the magnitudes of the neighbours along the exact gradient direction.
+    #[default]
+    Interpolated,
+    /// Round the gradient direction to the nearest multiple of 45° and compare with the neighbouring pixels.
+    Quantized,
+}
+
+/// The thresholds of the double thresholding.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Thresholds {
+    /// Pixels above `high * <strongest edge>` are edges.
+    pub high: f32,
+    /// Pixels above `low * high * <strongest edge>` are weak edges.
+    pub low: f32,
+}
+
+impl Default for Thresholds {
+    fn default() -> Self {
+        Self {
+            high: 0.2,
+            low: 0.7,
+        }
+    }
+}
+
+/// How the values of a stage are mapped before they are saved.
+///
+/// The steps are applied in the order of the fields: the absolute value, the normalisation, the
+/// transfer function and the inversion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct OutputEncoding {
+    pub transfer: TransferFunction,
+    /// Maps the smallest value of the image to `0` and the biggest to `1`.
+    pub normalize: bool,
+    /// Uses the absolute values, for signed stages like the gradients.
+    pub absolute: bool,
+    /// Maps `value` to `1 - value`, for black edges on white.
+    pub invert: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TransferFunction {
+    /// The values are stored as they are.
+    #[default]
+    Linear,
+    /// `value^(1 / 2.2)`.
+    Gamma,
+    /// The sRGB transfer function.
+    Srgb,
+}
+
+/// How `apply_overlay` draws the edges over the input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OverlayParams {
+    /// RGBA colour of the edges which were already edges before the edge tracking. The alpha blends
+    /// it with the input.
+    pub strong_color: [u8; 4],
+    /// RGBA colour of the weak edges which were kept by the edge tracking.
+    pub weak_color: [u8; 4],
+    /// Radius (in pixels) by which the edges are dilated. `0` draws them one pixel wide.
+    pub dilation: u32,
+}
+
+impl Default for OverlayParams {
+    fn default() -> Self {
+        Self {
+            strong_color: [255, 0, 0, 255],
+            weak_color: [255, 255, 0, 255],
+            dilation: 0,
+        }
+    }
+}
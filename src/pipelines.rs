@@ -0,0 +1,191 @@
+use std::sync::OnceLock;
+
+use wgpu::include_wgsl;
+
+use crate::Renderer;
+
+/// Cache for the compiled compute pipelines of the stages.
+///
+/// Without it, every stage compiles its shaders again each time it's applied. A renderer which
+/// processes more than one image should hand out one through `Renderer::pipelines`.
+#[derive(Debug, Default)]
+pub struct Pipelines {
+    grayscale: OnceLock<wgpu::ComputePipeline>,
+    gaussian_filter: OnceLock<wgpu::ComputePipeline>,
+    sobel_operators: OnceLock<wgpu::ComputePipeline>,
+    magnitude: OnceLock<wgpu::ComputePipeline>,
+    non_maximum_suppression: OnceLock<wgpu::ComputePipeline>,
+    max_value: OnceLock<wgpu::ComputePipeline>,
+    double_thresholding: OnceLock<wgpu::ComputePipeline>,
+    edge_tracking: OnceLock<wgpu::ComputePipeline>,
+    edge_tracking_dispatch: OnceLock<wgpu::ComputePipeline>,
+    hough_lines: OnceLock<wgpu::ComputePipeline>,
+    hough_circles: OnceLock<wgpu::ComputePipeline>,
+    min_max: OnceLock<wgpu::ComputePipeline>,
+    output_encoding: OnceLock<wgpu::ComputePipeline>,
+    direction: OnceLock<wgpu::ComputePipeline>,
+    overlay: OnceLock<wgpu::ComputePipeline>,
+}
+
+impl Pipelines {
+    pub(crate) fn grayscale(renderer: &dyn Renderer) -> wgpu::ComputePipeline {
+        get_or_create(
+            renderer,
+            |pipelines| &pipelines.grayscale,
+            "Grayscale: Compute pipeline",
+            include_wgsl!("./grayscale.wgsl"),
+        )
+    }
+
+    pub(crate) fn gaussian_filter(renderer: &dyn Renderer) -> wgpu::ComputePipeline {
+        get_or_create(
+            renderer,
+            |pipelines| &pipelines.gaussian_filter,
+            "Gaussian filter pipeline",
+            include_wgsl!("./kernels.wgsl"),
+        )
+    }
+
+    pub(crate) fn sobel_operators(renderer: &dyn Renderer) -> wgpu::ComputePipeline {
+        get_or_create(
+            renderer,
+            |pipelines| &pipelines.sobel_operators,
+            "Soeber: Compute pipeline",
+            include_wgsl!("./kernels.wgsl"),
+        )
+    }
+
+    pub(crate) fn magnitude(renderer: &dyn Renderer) -> wgpu::ComputePipeline {
+        get_or_create(
+            renderer,
+            |pipelines| &pipelines.magnitude,
+            "Magnitude: Compute pipeline",
+            include_wgsl!("./magnitude.wgsl"),
+        )
+    }
+
+    pub(crate) fn non_maximum_suppression(renderer: &dyn Renderer) -> wgpu::ComputePipeline {
+        get_or_create(
+            renderer,
+            |pipelines| &pipelines.non_maximum_suppression,
+            "Non maximum suppression: Compute pipeline",
+            include_wgsl!("./non_maximum_suppression.wgsl"),
+        )
+    }
+
+    pub(crate) fn max_value(renderer: &dyn Renderer) -> wgpu::ComputePipeline {
+        get_or_create(
+            renderer,
+            |pipelines| &pipelines.max_value,
+            "Double Threshold: Max value pipeline",
+            include_wgsl!("./max_value.wgsl"),
+        )
+    }
+
+    pub(crate) fn double_thresholding(renderer: &dyn Renderer) -> wgpu::ComputePipeline {
+        get_or_create(
+            renderer,
+            |pipelines| &pipelines.double_thresholding,
+            "Double Threshold: Compute pipeline",
+            include_wgsl!("./double_threshoulding.wgsl"),
+        )
+    }
+
+    pub(crate) fn edge_tracking(renderer: &dyn Renderer) -> wgpu::ComputePipeline {
+        get_or_create(
+            renderer,
+            |pipelines| &pipelines.edge_tracking,
+            "Edge tracking: Compute pipeline",
+            include_wgsl!("./edge_tracking.wgsl"),
+        )
+    }
+
+    pub(crate) fn edge_tracking_dispatch(renderer: &dyn Renderer) -> wgpu::ComputePipeline {
+        get_or_create(
+            renderer,
+            |pipelines| &pipelines.edge_tracking_dispatch,
+            "Edge tracking: Dispatch pipeline",
+            include_wgsl!("./edge_tracking_dispatch.wgsl"),
+        )
+    }
+
+    pub(crate) fn hough_lines(renderer: &dyn Renderer) -> wgpu::ComputePipeline {
+        get_or_create(
+            renderer,
+            |pipelines| &pipelines.hough_lines,
+            "Hough lines: Compute pipeline",
+            include_wgsl!("./hough_lines.wgsl"),
+        )
+    }
+
+    pub(crate) fn hough_circles(renderer: &dyn Renderer) -> wgpu::ComputePipeline {
+        get_or_create(
+            renderer,
+            |pipelines| &pipelines.hough_circles,
+            "Hough circles: Compute pipeline",
+            include_wgsl!("./hough_circles.wgsl"),
+        )
+    }
+
+    pub(crate) fn min_max(renderer: &dyn Renderer) -> wgpu::ComputePipeline {
+        get_or_create(
+            renderer,
+            |pipelines| &pipelines.min_max,
+            "Output encoding: Min max pipeline",
+            include_wgsl!("./min_max.wgsl"),
+        )
+    }
+
+    pub(crate) fn output_encoding(renderer: &dyn Renderer) -> wgpu::ComputePipeline {
+        get_or_create(
+            renderer,
+            |pipelines| &pipelines.output_encoding,
+            "Output encoding: Compute pipeline",
+            include_wgsl!("./encoding.wgsl"),
+        )
+    }
+
+    pub(crate) fn direction(renderer: &dyn Renderer) -> wgpu::ComputePipeline {
+        get_or_create(
+            renderer,
+            |pipelines| &pipelines.direction,
+            "Direction: Compute pipeline",
+            include_wgsl!("./direction.wgsl"),
+        )
+    }
+
+    pub(crate) fn overlay(renderer: &dyn Renderer) -> wgpu::ComputePipeline {
+        get_or_create(
+            renderer,
+            |pipelines| &pipelines.overlay,
+            "Overlay: Compute pipeline",
+            include_wgsl!("./overlay.wgsl"),
+        )
+    }
+}
+
+fn get_or_create(
+    renderer: &dyn Renderer,
+    cell: fn(&Pipelines) -> &OnceLock<wgpu::ComputePipeline>,
+    label: &str,
+    shader: wgpu::ShaderModuleDescriptor,
+) -> wgpu::ComputePipeline {
+    let create = || {
+        let device = renderer.device();
+        let shader = device.create_shader_module(shader);
+
+        device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some(label),
+            layout: None,
+            module: &shader,
+            entry_point: None,
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+            cache: None,
+        })
+    };
+
+    match renderer.pipelines() {
+        Some(pipelines) => cell(pipelines).get_or_init(create).clone(),
+        None => create(),
+    }
+}
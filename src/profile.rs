@@ -0,0 +1,188 @@
+//! Per-stage timing of the compute passes.
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use crate::Renderer;
+
+/// Amount of compute passes which can be timed with timestamp queries until `Profiler::finish`.
+/// Passes beyond it aren't timed.
+const MAX_PASSES: u32 = 256;
+
+/// How the durations of a `Profile` were measured.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Timer {
+    /// Timestamps which the GPU writes at the beginning and the end of each compute pass.
+    GpuTimestamps,
+    /// Wall clock time from the submission of a stage until the GPU finished it, including the
+    /// submission overhead. Used if the device doesn't support `TIMESTAMP_QUERY`.
+    CpuWallClock,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StageTime {
+    pub stage: &'static str,
+    pub duration: Duration,
+}
+
+/// Durations of the stages in the order they were applied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Profile {
+    pub timer: Timer,
+    pub stages: Vec<StageTime>,
+}
+
+impl Profile {
+    pub fn total(&self) -> Duration {
+        self.stages.iter().map(|stage| stage.duration).sum()
+    }
+}
+
+/// Records the duration of every stage which is applied with the renderer it's handed out by,
+/// see `Renderer::profiler`.
+///
+/// Uses timestamp queries if the device was created with `TIMESTAMP_QUERY` and falls back to
+/// waiting for the GPU after every stage otherwise.
+#[derive(Debug)]
+pub struct Profiler {
+    timestamps: Option<Timestamps>,
+    stages: Mutex<Vec<StageTime>>,
+}
+
+#[derive(Debug)]
+struct Timestamps {
+    query_set: wgpu::QuerySet,
+    resolve_buffer: wgpu::Buffer,
+}
+
+impl Profiler {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let timestamps = device
+            .features()
+            .contains(wgpu::Features::TIMESTAMP_QUERY)
+            .then(|| Timestamps {
+                query_set: device.create_query_set(&wgpu::QuerySetDescriptor {
+                    label: Some("Profiler: Query set"),
+                    ty: wgpu::QueryType::Timestamp,
+                    count: MAX_PASSES * 2,
+                }),
+                resolve_buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("Profiler: Resolve buffer"),
+                    size: (MAX_PASSES * 2) as u64 * wgpu::QUERY_SIZE as u64,
+                    usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+                    mapped_at_creation: false,
+                }),
+            });
+
+        Self {
+            timestamps,
+            stages: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn timer(&self) -> Timer {
+        match self.timestamps {
+            Some(_) => Timer::GpuTimestamps,
+            None => Timer::CpuWallClock,
+        }
+    }
+
+    /// Returns the durations of the stages which were applied since the last call.
+    pub fn finish(&self, renderer: &dyn Renderer) -> Profile {
+        let mut stages = std::mem::take(&mut *self.stages.lock().unwrap());
+
+        if let Some(timestamps) = &self.timestamps
+            && !stages.is_empty()
+        {
+            let mut encoder =
+                renderer
+                    .device()
+                    .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                        label: Some("Profiler: Command encoder"),
+                    });
+            encoder.resolve_query_set(
+                &timestamps.query_set,
+                0..stages.len() as u32 * 2,
+                &timestamps.resolve_buffer,
+                0,
+            );
+            renderer.queue().submit(std::iter::once(encoder.finish()));
+
+            let bytes = crate::read_buffer(renderer, &timestamps.resolve_buffer);
+            let ticks: &[u64] = bytemuck::cast_slice(&bytes);
+            let period = renderer.queue().get_timestamp_period() as f64;
+
+            for (stage, ticks) in stages.iter_mut().zip(ticks.chunks(2)) {
+                let nanos = ticks[1].saturating_sub(ticks[0]) as f64 * period;
+                stage.duration = Duration::from_nanos(nanos as u64);
+            }
+        }
+
+        Profile {
+            timer: self.timer(),
+            stages,
+        }
+    }
+
+    fn timestamp_writes(
+        &self,
+        stage: &'static str,
+    ) -> Option<wgpu::ComputePassTimestampWrites<'_>> {
+        let timestamps = self.timestamps.as_ref()?;
+
+        let mut stages = self.stages.lock().unwrap();
+        let index = stages.len() as u32;
+        if index >= MAX_PASSES {
+            return None;
+        }
+
+        // the duration is known once the queries are resolved
+        stages.push(StageTime {
+            stage,
+            duration: Duration::ZERO,
+        });
+
+        Some(wgpu::ComputePassTimestampWrites {
+            query_set: &timestamps.query_set,
+            beginning_of_pass_write_index: Some(index * 2),
+            end_of_pass_write_index: Some(index * 2 + 1),
+        })
+    }
+
+    fn submit(&self, renderer: &dyn Renderer, stage: &'static str, commands: wgpu::CommandBuffer) {
+        if self.timestamps.is_some() {
+            renderer.queue().submit(std::iter::once(commands));
+            return;
+        }
+
+        // the previous work mustn't count towards this stage
+        renderer.device().poll(wgpu::PollType::Wait).unwrap();
+        let start = Instant::now();
+        renderer.queue().submit(std::iter::once(commands));
+        renderer.device().poll(wgpu::PollType::Wait).unwrap();
+
+        self.stages.lock().unwrap().push(StageTime {
+            stage,
+            duration: start.elapsed(),
+        });
+    }
+}
+
+/// Timestamp writes for the compute pass of `stage`, if the renderer profiles with timestamp queries.
+pub(crate) fn timestamp_writes<'a>(
+    renderer: &'a dyn Renderer,
+    stage: &'static str,
+) -> Option<wgpu::ComputePassTimestampWrites<'a>> {
+    renderer.profiler()?.timestamp_writes(stage)
+}
+
+/// Submits the commands of `stage`, which have to include at most one timed compute pass.
+pub(crate) fn submit(renderer: &dyn Renderer, stage: &'static str, encoder: wgpu::CommandEncoder) {
+    match renderer.profiler() {
+        Some(profiler) => profiler.submit(renderer, stage, encoder.finish()),
+        None => {
+            renderer.queue().submit(std::iter::once(encoder.finish()));
+        }
+    }
+}
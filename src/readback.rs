@@ -0,0 +1,129 @@
+//! Reading textures back without blocking a thread.
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    task::{Context, Poll, Waker},
+    thread::JoinHandle,
+    time::Duration,
+};
+
+use crate::{Renderer, copy_texture_to_buffer, remove_row_padding};
+
+/// How long the poller sleeps if the GPU has nothing to do.
+const IDLE_INTERVAL: Duration = Duration::from_millis(1);
+
+/// Polls a device on its own thread, so the futures of the async functions get completed.
+///
+/// wgpu only reports finished work while the device is polled. Without a poller (or another
+/// thread which calls `Device::poll`), the async functions never finish. The thread stops when the
+/// poller is dropped.
+#[derive(Debug)]
+pub struct DevicePoller {
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl DevicePoller {
+    pub fn new(device: wgpu::Device) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let thread = std::thread::Builder::new()
+            .name("wgpu device poller".to_string())
+            .spawn({
+                let stop = stop.clone();
+                move || {
+                    while !stop.load(Ordering::Relaxed) {
+                        // waiting without a timeout can't fail
+                        let status = device.poll(wgpu::PollType::Wait).unwrap();
+                        if status.is_queue_empty() {
+                            std::thread::sleep(IDLE_INTERVAL);
+                        }
+                    }
+                }
+            })
+            .expect("Spawn device poller thread");
+
+        Self {
+            stop,
+            thread: Some(thread),
+        }
+    }
+}
+
+impl Drop for DevicePoller {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Same as `read_texture`, but waits for the GPU without blocking the thread.
+///
+/// The device has to be polled by someone else, for example by a `DevicePoller`.
+pub async fn read_texture_async(
+    renderer: &(dyn Renderer + Sync),
+    texture: &wgpu::Texture,
+) -> Vec<f32> {
+    if texture.format() != wgpu::TextureFormat::R32Float {
+        panic!("Texture has format: '{:?}'", texture.format());
+    }
+
+    let (buffer, padded_bytes_per_row) = copy_texture_to_buffer(renderer, texture);
+    let bytes = map_buffer_async(&buffer).await;
+
+    remove_row_padding(&bytes, texture.width(), padded_bytes_per_row)
+}
+
+async fn map_buffer_async(buffer: &wgpu::Buffer) -> Vec<u8> {
+    let slice = buffer.slice(..);
+
+    let state = Arc::new(Mutex::new(MapState::default()));
+    slice.map_async(wgpu::MapMode::Read, {
+        let state = state.clone();
+        move |result| {
+            let mut state = state.lock().unwrap();
+            state.result = Some(result);
+            if let Some(waker) = state.waker.take() {
+                waker.wake();
+            }
+        }
+    });
+    MapFuture { state }.await.unwrap();
+
+    let bytes = slice.get_mapped_range().to_vec();
+    buffer.unmap();
+
+    bytes
+}
+
+#[derive(Default)]
+struct MapState {
+    result: Option<Result<(), wgpu::BufferAsyncError>>,
+    waker: Option<Waker>,
+}
+
+/// Finishes once the callback of `map_async` was called.
+struct MapFuture {
+    state: Arc<Mutex<MapState>>,
+}
+
+impl Future for MapFuture {
+    type Output = Result<(), wgpu::BufferAsyncError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.state.lock().unwrap();
+        match state.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
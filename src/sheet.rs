@@ -0,0 +1,189 @@
+//! Contact sheets and animations of the stages, for documentation and bug reports.
+//!
+//! Each image gets a bar above it with its label, written with a small built-in bitmap font which
+//! knows lower case letters, digits, spaces, `_`, `-` and `.`.
+use std::{io::Write, path::Path, time::Duration};
+
+use image::{
+    Delay, ImageResult, Rgba, RgbaImage,
+    codecs::gif::{GifEncoder, Repeat},
+};
+
+/// Each pixel of the font becomes a square of this size.
+const FONT_SCALE: u32 = 2;
+const GLYPH_WIDTH: u32 = 5;
+const GLYPH_HEIGHT: u32 = 7;
+/// Space around the text of a label and between the cells of a sheet.
+const PADDING: u32 = 4;
+
+const BACKGROUND: Rgba<u8> = Rgba([0, 0, 0, 255]);
+const TEXT: Rgba<u8> = Rgba([255, 255, 255, 255]);
+
+#[derive(Debug, Clone)]
+pub struct LabelledImage {
+    pub label: String,
+    pub image: RgbaImage,
+}
+
+impl LabelledImage {
+    pub fn new(label: impl Into<String>, image: RgbaImage) -> Self {
+        Self {
+            label: label.into(),
+            image,
+        }
+    }
+
+    /// The image below a bar with the label.
+    pub fn render(&self) -> RgbaImage {
+        let bar_height = label_height();
+
+        let mut img = RgbaImage::from_pixel(
+            self.image.width(),
+            self.image.height() + bar_height,
+            BACKGROUND,
+        );
+        draw_text(&mut img, PADDING, PADDING, &self.label);
+        image::imageops::replace(&mut img, &self.image, 0, bar_height as i64);
+
+        img
+    }
+}
+
+/// Places the images in a grid with the given amount of columns, in the order of `images`.
+///
+/// The cells have the size of the biggest image.
+pub fn contact_sheet(images: &[LabelledImage], columns: u32) -> RgbaImage {
+    let columns = columns.clamp(1, images.len().max(1) as u32);
+    let rows = (images.len() as u32).div_ceil(columns);
+
+    let cells: Vec<RgbaImage> = images.iter().map(LabelledImage::render).collect();
+    let cell_width = cells.iter().map(RgbaImage::width).max().unwrap_or(0);
+    let cell_height = cells.iter().map(RgbaImage::height).max().unwrap_or(0);
+
+    let mut sheet = RgbaImage::from_pixel(
+        columns * cell_width + (columns + 1) * PADDING,
+        rows * cell_height + (rows + 1) * PADDING,
+        BACKGROUND,
+    );
+    for (index, cell) in cells.iter().enumerate() {
+        let (column, row) = (index as u32 % columns, index as u32 / columns);
+        image::imageops::replace(
+            &mut sheet,
+            cell,
+            (PADDING + column * (cell_width + PADDING)) as i64,
+            (PADDING + row * (cell_height + PADDING)) as i64,
+        );
+    }
+
+    sheet
+}
+
+/// Writes an endlessly repeating GIF which shows each image for `delay`.
+///
+/// The frames have the size of the biggest image, smaller ones are placed in the top left corner.
+pub fn write_gif<W: Write>(
+    writer: W,
+    images: &[LabelledImage],
+    delay: Duration,
+) -> ImageResult<()> {
+    let frames: Vec<RgbaImage> = images.iter().map(LabelledImage::render).collect();
+    let width = frames.iter().map(RgbaImage::width).max().unwrap_or(0);
+    let height = frames.iter().map(RgbaImage::height).max().unwrap_or(0);
+
+    let mut encoder = GifEncoder::new_with_speed(writer, 10);
+    encoder.set_repeat(Repeat::Infinite)?;
+    encoder.encode_frames(frames.into_iter().map(|frame| {
+        let mut canvas = RgbaImage::from_pixel(width, height, BACKGROUND);
+        image::imageops::replace(&mut canvas, &frame, 0, 0);
+
+        image::Frame::from_parts(canvas, 0, 0, Delay::from_saturating_duration(delay))
+    }))
+}
+
+/// Writes a GIF file, see `write_gif`.
+pub fn save_gif(
+    path: impl AsRef<Path>,
+    images: &[LabelledImage],
+    delay: Duration,
+) -> ImageResult<()> {
+    let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+    write_gif(file, images, delay)
+}
+
+/// Height of the bar with the label.
+pub fn label_height() -> u32 {
+    GLYPH_HEIGHT * FONT_SCALE + 2 * PADDING
+}
+
+fn draw_text(img: &mut RgbaImage, x: u32, y: u32, text: &str) {
+    for (index, char) in text.chars().enumerate() {
+        // one empty column between the glyphs
+        let glyph_x = x + index as u32 * (GLYPH_WIDTH + 1) * FONT_SCALE;
+
+        for (row, bits) in glyph(char.to_ascii_lowercase()).iter().enumerate() {
+            for column in 0..GLYPH_WIDTH {
+                if bits & (1 << (GLYPH_WIDTH - 1 - column)) == 0 {
+                    continue;
+                }
+
+                for dy in 0..FONT_SCALE {
+                    for dx in 0..FONT_SCALE {
+                        let px = glyph_x + column * FONT_SCALE + dx;
+                        let py = y + row as u32 * FONT_SCALE + dy;
+                        if px < img.width() && py < img.height() {
+                            img.put_pixel(px, py, TEXT);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The rows of a glyph, the lowest 5 bits of each row are its pixels from left to right.
+/// Unknown characters are empty.
+#[rustfmt::skip]
+fn glyph(char: char) -> [u8; 7] {
+    match char {
+        '0' => [0b01110, 0b10001, 0b10011, 0b10101, 0b11001, 0b10001, 0b01110],
+        '1' => [0b00100, 0b01100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110],
+        '2' => [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b01000, 0b11111],
+        '3' => [0b11111, 0b00010, 0b00100, 0b00010, 0b00001, 0b10001, 0b01110],
+        '4' => [0b00010, 0b00110, 0b01010, 0b10010, 0b11111, 0b00010, 0b00010],
+        '5' => [0b11111, 0b10000, 0b11110, 0b00001, 0b00001, 0b10001, 0b01110],
+        '6' => [0b00110, 0b01000, 0b10000, 0b11110, 0b10001, 0b10001, 0b01110],
+        '7' => [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b01000, 0b01000],
+        '8' => [0b01110, 0b10001, 0b10001, 0b01110, 0b10001, 0b10001, 0b01110],
+        '9' => [0b01110, 0b10001, 0b10001, 0b01111, 0b00001, 0b00010, 0b01100],
+        'a' => [0b00000, 0b00000, 0b01110, 0b00001, 0b01111, 0b10001, 0b01111],
+        'b' => [0b10000, 0b10000, 0b10110, 0b11001, 0b10001, 0b10001, 0b11110],
+        'c' => [0b00000, 0b00000, 0b01110, 0b10000, 0b10000, 0b10001, 0b01110],
+        'd' => [0b00001, 0b00001, 0b01101, 0b10011, 0b10001, 0b10001, 0b01111],
+        'e' => [0b00000, 0b00000, 0b01110, 0b10001, 0b11111, 0b10000, 0b01110],
+        'f' => [0b00110, 0b01001, 0b01000, 0b11100, 0b01000, 0b01000, 0b01000],
+        'g' => [0b00000, 0b01111, 0b10001, 0b10001, 0b01111, 0b00001, 0b01110],
+        'h' => [0b10000, 0b10000, 0b10110, 0b11001, 0b10001, 0b10001, 0b10001],
+        'i' => [0b00100, 0b00000, 0b01100, 0b00100, 0b00100, 0b00100, 0b01110],
+        'j' => [0b00010, 0b00000, 0b00110, 0b00010, 0b00010, 0b10010, 0b01100],
+        'k' => [0b10000, 0b10000, 0b10010, 0b10100, 0b11000, 0b10100, 0b10010],
+        'l' => [0b01100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110],
+        'm' => [0b00000, 0b00000, 0b11010, 0b10101, 0b10101, 0b10001, 0b10001],
+        'n' => [0b00000, 0b00000, 0b10110, 0b11001, 0b10001, 0b10001, 0b10001],
+        'o' => [0b00000, 0b00000, 0b01110, 0b10001, 0b10001, 0b10001, 0b01110],
+        'p' => [0b00000, 0b00000, 0b11110, 0b10001, 0b11110, 0b10000, 0b10000],
+        'q' => [0b00000, 0b00000, 0b01101, 0b10011, 0b01111, 0b00001, 0b00001],
+        'r' => [0b00000, 0b00000, 0b10110, 0b11001, 0b10000, 0b10000, 0b10000],
+        's' => [0b00000, 0b00000, 0b01110, 0b10000, 0b01110, 0b00001, 0b11110],
+        't' => [0b01000, 0b01000, 0b11100, 0b01000, 0b01000, 0b01001, 0b00110],
+        'u' => [0b00000, 0b00000, 0b10001, 0b10001, 0b10001, 0b10011, 0b01101],
+        'v' => [0b00000, 0b00000, 0b10001, 0b10001, 0b10001, 0b01010, 0b00100],
+        'w' => [0b00000, 0b00000, 0b10001, 0b10001, 0b10101, 0b10101, 0b01010],
+        'x' => [0b00000, 0b00000, 0b10001, 0b01010, 0b00100, 0b01010, 0b10001],
+        'y' => [0b00000, 0b00000, 0b10001, 0b10001, 0b01111, 0b00001, 0b01110],
+        'z' => [0b00000, 0b00000, 0b11111, 0b00010, 0b00100, 0b01000, 0b11111],
+        '_' => [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b11111],
+        '-' => [0b00000, 0b00000, 0b00000, 0b11111, 0b00000, 0b00000, 0b00000],
+        '.' => [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b01100],
+        _ => [0; 7],
+    }
+}
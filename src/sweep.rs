@@ -0,0 +1,214 @@
+use std::{
+    collections::HashMap,
+    ffi::OsString,
+    io::Write,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use clap::ValueEnum;
+use image::{GrayImage, ImageReader};
+use wgpu_canny_edge_detection::{
+    CannyParams, EdgeMasks, Renderer as RendererTrait, Thresholds, apply_canny,
+    create_rgba_texture, metrics::EdgeMetrics, read_texture,
+};
+
+use crate::{Renderer, cli::SweepArgs};
+
+struct Sample {
+    input_texture: wgpu::Texture,
+    ground_truth: GrayImage,
+}
+
+/// Applies the edge detection with each combination of the parameter grid to every image and writes
+/// the mean quality metrics and time per combination to a CSV file.
+///
+/// The images are uploaded once and the compiled pipelines are shared by all combinations.
+pub fn run(renderer: &Renderer, args: &SweepArgs) {
+    let samples = load_samples(renderer, args);
+    if samples.is_empty() {
+        eprintln!("No images with a ground truth found");
+        std::process::exit(1);
+    }
+
+    let mut csv = std::fs::File::create(&args.output).expect("Create CSV file");
+    writeln!(
+        csv,
+        "gradient_operator,sigma,high_threshold,low_threshold,images,pratt_fom,precision,recall,f1,hausdorff,time_ms"
+    )
+    .unwrap();
+
+    let combinations = args.gradient_operator.len()
+        * args.sigma.len()
+        * args.high_threshold.len()
+        * args.low_threshold.len();
+    let mut combination = 0;
+
+    for &gradient_operator in args.gradient_operator.iter() {
+        for &sigma in args.sigma.iter() {
+            for &high in args.high_threshold.iter() {
+                for &low in args.low_threshold.iter() {
+                    combination += 1;
+                    let operator_name = gradient_operator.to_possible_value().unwrap();
+                    let operator_name = operator_name.get_name();
+                    println!(
+                        "[{combination}/{combinations}] operator: {operator_name}, sigma: {sigma}, high: {high}, low: {low}"
+                    );
+
+                    let params = CannyParams {
+                        sigma,
+                        kernel_radius: args.kernel_radius,
+                        gradient_operator: gradient_operator.into(),
+                        nms_mode: args.nms_mode.into(),
+                        thresholds: Thresholds { high, low },
+                    };
+
+                    let (metrics, time) = evaluate_combination(renderer, &samples, args, &params);
+                    writeln!(
+                        csv,
+                        "{operator_name},{sigma},{high},{low},{},{},{},{},{},{},{:.3}",
+                        samples.len(),
+                        metrics.pratt_fom,
+                        metrics.precision,
+                        metrics.recall,
+                        metrics.f1,
+                        metrics.hausdorff,
+                        time.as_secs_f64() * 1000.
+                    )
+                    .unwrap();
+                }
+            }
+        }
+    }
+
+    println!("Wrote {}", args.output.display());
+}
+
+/// Returns the mean metrics and the mean time per image.
+fn evaluate_combination(
+    renderer: &Renderer,
+    samples: &[Sample],
+    args: &SweepArgs,
+    params: &CannyParams,
+) -> (EdgeMetrics, Duration) {
+    let mut sum = EdgeMetrics {
+        pratt_fom: 0.,
+        precision: 0.,
+        recall: 0.,
+        f1: 0.,
+        hausdorff: 0.,
+    };
+    let mut total_time = Duration::ZERO;
+
+    for sample in samples {
+        let start = Instant::now();
+        let edges = apply_canny(
+            renderer,
+            sample
+                .input_texture
+                .create_view(&wgpu::TextureViewDescriptor::default()),
+            params,
+            None,
+            &EdgeMasks::default(),
+        )
+        .unwrap();
+        let values = read_texture(renderer, &edges);
+        total_time += start.elapsed();
+
+        // weak edges which weren't connected to an edge are left over with a value of 0.5
+        let edges = GrayImage::from_raw(
+            edges.width(),
+            edges.height(),
+            values
+                .into_iter()
+                .map(|value| if value >= 1. { u8::MAX } else { 0 })
+                .collect(),
+        )
+        .unwrap();
+
+        let metrics = args.metrics.evaluate(&edges, &sample.ground_truth);
+        sum.pratt_fom += metrics.pratt_fom;
+        sum.precision += metrics.precision;
+        sum.recall += metrics.recall;
+        sum.f1 += metrics.f1;
+        sum.hausdorff += metrics.hausdorff;
+    }
+
+    let amount = samples.len() as f32;
+    let mean = EdgeMetrics {
+        pratt_fom: sum.pratt_fom / amount,
+        precision: sum.precision / amount,
+        recall: sum.recall / amount,
+        f1: sum.f1 / amount,
+        hausdorff: sum.hausdorff / amount,
+    };
+
+    (mean, total_time / samples.len() as u32)
+}
+
+/// Loads the images which have a ground truth and uploads them.
+fn load_samples(renderer: &Renderer, args: &SweepArgs) -> Vec<Sample> {
+    let ground_truths: HashMap<OsString, PathBuf> = std::fs::read_dir(&args.ground_truth_dir)
+        .expect("Read ground truth dir")
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file())
+        .filter_map(|path| Some((path.file_stem()?.to_os_string(), path)))
+        .collect();
+
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(&args.input_dir)
+        .expect("Read input dir")
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file())
+        .filter(|path| {
+            path.file_name()
+                .is_some_and(|name| args.glob.matches(&name.to_string_lossy()))
+        })
+        .collect();
+    paths.sort();
+
+    let max_texture_dimension = renderer.device().limits().max_texture_dimension_2d;
+
+    let mut samples = Vec::new();
+    for path in paths {
+        let Some(ground_truth_path) = path.file_stem().and_then(|stem| ground_truths.get(stem))
+        else {
+            eprintln!("Skipping {}: no ground truth", path.display());
+            continue;
+        };
+
+        let (image, ground_truth) = match (decode(&path), decode(ground_truth_path)) {
+            (Ok(image), Ok(ground_truth)) => (image.to_rgba8(), ground_truth.to_luma8()),
+            (Err(err), _) | (_, Err(err)) => {
+                eprintln!("Skipping {}: {err}", path.display());
+                continue;
+            }
+        };
+
+        if image.dimensions() != ground_truth.dimensions() {
+            eprintln!(
+                "Skipping {}: the ground truth has a different size",
+                path.display()
+            );
+            continue;
+        }
+        if image.width() > max_texture_dimension || image.height() > max_texture_dimension {
+            eprintln!("Skipping {}: too big for a texture", path.display());
+            continue;
+        }
+
+        samples.push(Sample {
+            input_texture: create_rgba_texture(renderer, &image),
+            ground_truth,
+        });
+    }
+
+    println!("Sweeping over {} images", samples.len());
+
+    samples
+}
+
+fn decode(path: &Path) -> Result<image::DynamicImage, String> {
+    ImageReader::open(path)
+        .map_err(|err| err.to_string())
+        .and_then(|reader| reader.decode().map_err(|err| err.to_string()))
+}
@@ -0,0 +1,176 @@
+//! Generators for images whose edges are known.
+//!
+//! Each generator returns the (gray) image and a mask of the pixels which are crossed by an edge.
+//! The shapes are anti-aliased and may be placed at fractional positions.
+use image::{GrayImage, Luma, Rgba, RgbaImage};
+
+/// Gray level of the shapes.
+pub const DARK: u8 = 96;
+/// Gray level of the background.
+pub const LIGHT: u8 = 160;
+
+/// Amount of samples per axis and pixel for the anti-aliasing.
+const SUBSAMPLES: u32 = 4;
+
+/// An image and its edges.
+#[derive(Debug, Clone)]
+pub struct SyntheticImage {
+    pub image: RgbaImage,
+    /// Pixels which are crossed by an edge have the value `255`, every other pixel is `0`.
+    pub edges: GrayImage,
+}
+
+impl SyntheticImage {
+    /// Adds gaussian noise with a standard deviation of `(LIGHT - DARK) / snr`.
+    ///
+    /// The same `seed` creates the same noise.
+    pub fn with_noise(mut self, snr: f32, seed: u64) -> Self {
+        let sigma = (LIGHT - DARK) as f32 / snr;
+        let mut rng = SplitMix64(seed);
+
+        for pixel in self.image.pixels_mut() {
+            let value = (pixel.0[0] as f32 + sigma * rng.next_gaussian())
+                .round()
+                .clamp(0., 255.) as u8;
+            *pixel = Rgba([value, value, value, 255]);
+        }
+
+        self
+    }
+}
+
+/// An image which is dark wherever `is_dark(x, y)` is true.
+///
+/// `is_dark` gets continuous coordinates, the pixel `(0, 0)` covers `[0, 1)²`. A pixel is an edge if
+/// the shape boundary is at most half a pixel (horizontally or vertically) away from its centre.
+pub fn pattern(width: u32, height: u32, is_dark: impl Fn(f32, f32) -> bool) -> SyntheticImage {
+    let image = render(width, height, |x, y| if is_dark(x, y) { 0. } else { 1. });
+
+    let edges = GrayImage::from_fn(width, height, |x, y| {
+        let (cx, cy) = (x as f32 + 0.5, y as f32 + 0.5);
+        let centre = is_dark(cx, cy);
+
+        let is_edge = [(0.5, 0.), (-0.5, 0.), (0., 0.5), (0., -0.5)]
+            .iter()
+            .any(|(dx, dy)| is_dark(cx + dx, cy + dy) != centre);
+        Luma([if is_edge { u8::MAX } else { 0 }])
+    });
+
+    SyntheticImage { image, edges }
+}
+
+/// A straight edge through `point` with the direction `angle` (in radians, `0` is horizontal).
+///
+/// The side in the direction of the normal `(sin(angle), -cos(angle))` is dark.
+pub fn step_edge(width: u32, height: u32, point: [f32; 2], angle: f32) -> SyntheticImage {
+    let (sin, cos) = angle.sin_cos();
+
+    pattern(width, height, |x, y| {
+        (x - point[0]) * sin - (y - point[1]) * cos > 0.
+    })
+}
+
+/// A dark disc.
+pub fn circle(width: u32, height: u32, centre: [f32; 2], radius: f32) -> SyntheticImage {
+    pattern(width, height, |x, y| {
+        (x - centre[0]).hypot(y - centre[1]) < radius
+    })
+}
+
+/// A vertical edge at `x` which goes linearly from dark to light over `ramp_width` pixels.
+///
+/// The edge is in the middle of the ramp.
+pub fn ramp(width: u32, height: u32, x: f32, ramp_width: f32) -> SyntheticImage {
+    let edges = pattern(width, height, |px, _| px < x).edges;
+    let image = render(width, height, |px, _| {
+        ((px - x) / ramp_width.max(f32::EPSILON) + 0.5).clamp(0., 1.)
+    });
+
+    SyntheticImage { image, edges }
+}
+
+/// Rows of random, blocky glyphs which are `glyph_size` pixels high.
+///
+/// The glyphs are made of strokes which are a fifth of the glyph size wide. The same `seed` creates
+/// the same text.
+pub fn text(width: u32, height: u32, glyph_size: u32, seed: u64) -> SyntheticImage {
+    // a glyph is a 3x5 grid of strokes, followed by a stroke of space
+    const COLUMNS: u32 = 3;
+    const ROWS: u32 = 5;
+
+    let stroke = glyph_size as f32 / ROWS as f32;
+    let (advance, line_height) = ((COLUMNS + 1) as f32 * stroke, (ROWS + 2) as f32 * stroke);
+    // keeps the strokes away from the pixel borders
+    let origin = stroke + 0.25;
+
+    let glyphs_per_line = ((width as f32 - 2. * origin) / advance).max(0.) as u32;
+    let lines = ((height as f32 - 2. * origin) / line_height).max(0.) as u32;
+
+    let mut rng = SplitMix64(seed);
+    let glyphs: Vec<u16> = (0..glyphs_per_line * lines)
+        .map(|_| rng.next_u64() as u16 & ((1 << (COLUMNS * ROWS)) - 1))
+        .collect();
+
+    pattern(width, height, |x, y| {
+        let (x, y) = (x - origin, y - origin);
+        if x < 0. || y < 0. {
+            return false;
+        }
+
+        let (glyph_x, glyph_y) = ((x / advance) as u32, (y / line_height) as u32);
+        if glyph_x >= glyphs_per_line || glyph_y >= lines {
+            return false;
+        }
+
+        let column = ((x - glyph_x as f32 * advance) / stroke) as u32;
+        let row = ((y - glyph_y as f32 * line_height) / stroke) as u32;
+        if column >= COLUMNS || row >= ROWS {
+            return false;
+        }
+
+        let glyph = glyphs[(glyph_x + glyph_y * glyphs_per_line) as usize];
+        glyph & (1 << (column + row * COLUMNS)) != 0
+    })
+}
+
+/// Renders the `intensity` (`0` is dark, `1` is light) with anti-aliasing.
+fn render(width: u32, height: u32, intensity: impl Fn(f32, f32) -> f32) -> RgbaImage {
+    RgbaImage::from_fn(width, height, |x, y| {
+        let mut sum = 0.;
+        for sy in 0..SUBSAMPLES {
+            for sx in 0..SUBSAMPLES {
+                let px = x as f32 + (sx as f32 + 0.5) / SUBSAMPLES as f32;
+                let py = y as f32 + (sy as f32 + 0.5) / SUBSAMPLES as f32;
+                sum += intensity(px, py);
+            }
+        }
+
+        let intensity = sum / (SUBSAMPLES * SUBSAMPLES) as f32;
+        let value = (DARK as f32 + (LIGHT - DARK) as f32 * intensity).round() as u8;
+        Rgba([value, value, value, 255])
+    })
+}
+
+/// Small, seedable random number generator, so the generators don't need a dependency.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a value in `(0, 1]`.
+    fn next_f32(&mut self) -> f32 {
+        ((self.next_u64() >> 40) + 1) as f32 / (1u64 << 24) as f32
+    }
+
+    /// Standard normal distributed value (Box-Muller transform).
+    fn next_gaussian(&mut self) -> f32 {
+        let (u1, u2) = (self.next_f32(), self.next_f32());
+        (-2. * u1.ln()).sqrt() * (std::f32::consts::TAU * u2).cos()
+    }
+}
@@ -0,0 +1,200 @@
+use image::{DynamicImage, GrayImage};
+
+use crate::{
+    CannyError, CannyParams, Renderer, Roi, apply_until_non_maximum_suppression,
+    cpu::{encode_max_value, threshold_bounds},
+    create_image_texture, read_texture,
+};
+
+const EDGE: u8 = u8::MAX;
+const WEAK_EDGE: u8 = u8::MAX / 2;
+/// Marks the pixels without a gradient until their class is known.
+const NO_GRADIENT: u8 = 1;
+
+/// A pixel which may still become a (weak) edge once the strongest edge of the image is known.
+struct Candidate {
+    x: u32,
+    y: u32,
+    value: f32,
+}
+
+/// Applies the canny edge detection to images which are too big for a single texture.
+///
+/// The image is split into tiles of (at most) `tile_size`x`tile_size` pixels which overlap by the
+/// apron of the filters, so the stitched result has no seams. The thresholds are relative to the
+/// strongest edge of the whole image and the edge tracking is done on the CPU over the whole
+/// stitched image, so edges are tracked across tile borders. The result is the same as the one of
+/// `apply_canny` with the edges set to `255` and everything else to `0`, except that chains of weak
+/// edges aren't cut after `edge_tracking_rounds`.
+///
+/// The pipeline runs once per tile. Since the strongest edge is only known after the last tile, the
+/// tiles keep 12 bytes for each pixel which is above the lower threshold of the strongest edge so
+/// far, which the non maximum suppression leaves to few pixels. Besides that, the memory holds the
+/// result (a byte per pixel), `img` and one padded tile at a time.
+///
+/// Returns an error if the tile size is zero or the apron alone doesn't fit into a texture.
+pub fn apply_canny_tiled(
+    renderer: &dyn Renderer,
+    img: &DynamicImage,
+    params: &CannyParams,
+    tile_size: u32,
+) -> Result<GrayImage, CannyError> {
+    if tile_size == 0 {
+        return Err(CannyError::InvalidParams(
+            "The tile size is zero".to_string(),
+        ));
+    }
+
+    let max_texture_dimension = renderer.device().limits().max_texture_dimension_2d;
+    let tile_size = tile_size.min(max_texture_dimension.saturating_sub(2 * params.apron()));
+    if tile_size == 0 {
+        return Err(CannyError::InvalidParams(format!(
+            "An apron of {} pixels doesn't leave room for tiles in textures of {max_texture_dimension} pixels",
+            params.apron()
+        )));
+    }
+
+    // 1. apply the pipeline to each tile, pixels below the lower threshold of the strongest edge so
+    // far are no edges whatever the strongest edge of the whole image is
+    let mut edges = GrayImage::new(img.width(), img.height());
+    let mut candidates = Vec::new();
+    let mut max_value = 0f32;
+    for tile in split_into_tiles(img.width(), img.height(), tile_size) {
+        let (non_maximum_suppression, padded_tile) = apply_tile(renderer, img, params, &tile)?;
+        let values = read_texture(renderer, &non_maximum_suppression);
+
+        let inner_tile = tile.relative_to(&padded_tile);
+        let values = &values;
+        let inner_values = || {
+            (0..inner_tile.height).flat_map(move |y| {
+                (0..inner_tile.width).map(move |x| {
+                    let idx = (y + inner_tile.y) * padded_tile.width + x + inner_tile.x;
+                    (tile.x + x, tile.y + y, values[idx as usize])
+                })
+            })
+        };
+
+        max_value = inner_values().fold(max_value, |max, (_, _, value)| max.max(value));
+        let (_, lower) = threshold_bounds(encode_max_value(max_value), params.thresholds);
+
+        for (x, y, value) in inner_values() {
+            if value == 0. {
+                edges.put_pixel(x, y, image::Luma([NO_GRADIENT]));
+            } else if value >= lower {
+                candidates.push(Candidate { x, y, value });
+            }
+        }
+    }
+
+    // 2. classify the pixels relative to the strongest edge, like `apply_double_thresholding`
+    let (upper, lower) = threshold_bounds(encode_max_value(max_value), params.thresholds);
+    let class = |value: f32| {
+        if value >= upper {
+            EDGE
+        } else if value < lower {
+            0
+        } else {
+            WEAK_EDGE
+        }
+    };
+
+    for candidate in candidates {
+        edges.put_pixel(
+            candidate.x,
+            candidate.y,
+            image::Luma([class(candidate.value)]),
+        );
+    }
+    let no_gradient_class = class(0.);
+    for pixel in edges.pixels_mut() {
+        if pixel.0[0] == NO_GRADIENT {
+            pixel.0[0] = no_gradient_class;
+        }
+    }
+
+    // 3. edge tracking over the whole image
+    track_edges(&mut edges);
+
+    Ok(edges)
+}
+
+fn split_into_tiles(width: u32, height: u32, tile_size: u32) -> Vec<Roi> {
+    let mut tiles = Vec::new();
+    for y in (0..height).step_by(tile_size as usize) {
+        for x in (0..width).step_by(tile_size as usize) {
+            tiles.push(Roi::new(
+                x,
+                y,
+                tile_size.min(width - x),
+                tile_size.min(height - y),
+            ));
+        }
+    }
+
+    tiles
+}
+
+/// Returns the non maximum suppression of the padded tile and the padded tile itself.
+fn apply_tile(
+    renderer: &dyn Renderer,
+    img: &DynamicImage,
+    params: &CannyParams,
+    tile: &Roi,
+) -> Result<(wgpu::Texture, Roi), CannyError> {
+    let padded_tile = tile.padded(params.apron(), img.width(), img.height());
+
+    let tile_img = img.crop_imm(
+        padded_tile.x,
+        padded_tile.y,
+        padded_tile.width,
+        padded_tile.height,
+    );
+    let input_texture = create_image_texture(renderer, &tile_img);
+
+    let non_maximum_suppression = apply_until_non_maximum_suppression(
+        renderer,
+        input_texture.create_view(&wgpu::TextureViewDescriptor::default()),
+        params,
+        None,
+    )?;
+
+    Ok((non_maximum_suppression, padded_tile))
+}
+
+/// Turns every weak edge which is connected to an edge into an edge and removes the remaining ones.
+///
+/// Like `apply_edge_tracking`, the weak edges are followed (8-connected), but always until the end
+/// of their chain.
+fn track_edges(edges: &mut GrayImage) {
+    let width = edges.width() as i64;
+    let height = edges.height() as i64;
+
+    let mut stack: Vec<(i64, i64)> = edges
+        .enumerate_pixels()
+        .filter(|(_, _, pixel)| pixel.0[0] == EDGE)
+        .map(|(x, y, _)| (x as i64, y as i64))
+        .collect();
+
+    while let Some((x, y)) = stack.pop() {
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                let (nx, ny) = (x + dx, y + dy);
+                if nx < 0 || nx >= width || ny < 0 || ny >= height {
+                    continue;
+                }
+
+                let pixel = edges.get_pixel_mut(nx as u32, ny as u32);
+                if pixel.0[0] == WEAK_EDGE {
+                    pixel.0[0] = EDGE;
+                    stack.push((nx, ny));
+                }
+            }
+        }
+    }
+
+    for pixel in edges.pixels_mut() {
+        if pixel.0[0] != EDGE {
+            pixel.0[0] = 0;
+        }
+    }
+}
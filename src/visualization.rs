@@ -0,0 +1,258 @@
+use image::RgbaImage;
+use wgpu::util::DeviceExt;
+
+use crate::{
+    CannyError, GpuInputFormat, OutputEncoding, OverlayParams, Pipelines, Renderer,
+    TransferFunction, apply_output_encoding, validate_input_texture,
+};
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct GpuOverlayParams {
+    strong_color: [f32; 4],
+    weak_color: [f32; 4],
+    dilation: u32,
+    _padding: [u32; 3],
+}
+
+impl From<&OverlayParams> for GpuOverlayParams {
+    fn from(params: &OverlayParams) -> Self {
+        let to_float = |color: [u8; 4]| color.map(|channel| channel as f32 / u8::MAX as f32);
+
+        Self {
+            strong_color: to_float(params.strong_color),
+            weak_color: to_float(params.weak_color),
+            dilation: params.dilation,
+            _padding: [0; 3],
+        }
+    }
+}
+
+/// Colours the gradients of `apply_magnitude_and_angle`: the direction becomes the hue and the
+/// magnitude (relative to the strongest gradient and gamma encoded) the brightness.
+///
+/// Returns a `Rgba8Unorm` texture, which can be read back with `read_rgba_image`. The colours are
+/// explained by `direction_legend`.
+pub fn apply_direction_visualization(
+    renderer: &dyn Renderer,
+    magnitudes: wgpu::TextureView,
+    radians: wgpu::TextureView,
+) -> wgpu::Texture {
+    const WORKGROUP_SIZE: u32 = 16;
+
+    let device = renderer.device();
+
+    let texture = magnitudes.texture();
+
+    let normalized = apply_output_encoding(
+        renderer,
+        magnitudes.clone(),
+        &OutputEncoding {
+            transfer: TransferFunction::Gamma,
+            normalize: true,
+            ..Default::default()
+        },
+    );
+
+    let out_texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Direction: Texture"),
+        size: texture.size(),
+        mip_level_count: texture.mip_level_count(),
+        sample_count: texture.sample_count(),
+        dimension: texture.dimension(),
+        format: wgpu::TextureFormat::Rgba8Unorm,
+        usage: wgpu::TextureUsages::STORAGE_BINDING
+            | wgpu::TextureUsages::COPY_SRC
+            | wgpu::TextureUsages::TEXTURE_BINDING,
+        view_formats: &[],
+    });
+
+    let pipeline = Pipelines::direction(renderer);
+
+    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Direction: Bind group"),
+        layout: &pipeline.get_bind_group_layout(0),
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(
+                    &normalized.create_view(&wgpu::TextureViewDescriptor::default()),
+                ),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::TextureView(&radians),
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: wgpu::BindingResource::TextureView(
+                    &out_texture.create_view(&wgpu::TextureViewDescriptor::default()),
+                ),
+            },
+        ],
+    });
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Direction: Command encoder"),
+    });
+
+    {
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Direction: Compute pass"),
+            timestamp_writes: crate::profile::timestamp_writes(renderer, "direction"),
+        });
+
+        pass.set_bind_group(0, &bind_group, &[]);
+        pass.set_pipeline(&pipeline);
+        pass.dispatch_workgroups(
+            texture.width().div_ceil(WORKGROUP_SIZE),
+            texture.height().div_ceil(WORKGROUP_SIZE),
+            1,
+        );
+    }
+
+    crate::profile::submit(renderer, "direction", encoder);
+
+    out_texture
+}
+
+/// A colour wheel for the output of `apply_direction_visualization`.
+///
+/// The angle around the centre is the gradient direction, with the y axis pointing down like in
+/// the image, and the distance to the centre is the magnitude. Pixels outside of the wheel are
+/// transparent.
+pub fn direction_legend(size: u32) -> RgbaImage {
+    let radius = size as f32 / 2.;
+
+    RgbaImage::from_fn(size, size, |x, y| {
+        let dx = x as f32 + 0.5 - radius;
+        let dy = y as f32 + 0.5 - radius;
+        let distance = dx.hypot(dy) / radius;
+        if distance > 1. {
+            return image::Rgba([0; 4]);
+        }
+
+        // gamma encoded like the magnitudes of the visualization
+        let [r, g, b] = direction_color(dy.atan2(dx), distance.powf(1. / 2.2));
+        image::Rgba([r, g, b, u8::MAX])
+    })
+}
+
+/// The colour of a gradient with the direction (in radians) and the encoded magnitude, like in
+/// `direction.wgsl`.
+pub(crate) fn direction_color(radians: f32, magnitude: f32) -> [u8; 3] {
+    let hue = (radians / std::f32::consts::TAU).rem_euclid(1.);
+    let rgb = [5., 3., 1.].map(|n: f32| {
+        let k = (n + hue * 6.) % 6.;
+        magnitude - magnitude * k.min(4. - k).clamp(0., 1.)
+    });
+
+    rgb.map(|channel| (channel.clamp(0., 1.) * u8::MAX as f32).round() as u8)
+}
+
+/// Draws the edges of `apply_edge_tracking` over the input of the edge detection.
+///
+/// Edges which were already edges in `double_thresholding` get the strong colour, the weak edges
+/// which were kept by the edge tracking the weak one. Since the edge tracking works in place
+/// without a ROI, it has to be applied with a ROI (of the whole image) to keep the thresholds
+/// apart. All textures have the size of the input. Returns a `Rgba8Unorm` texture, which can be
+/// read back with `read_rgba_image`.
+///
+/// Returns an error if the input isn't valid, see `validate_input_texture`.
+pub fn apply_overlay(
+    renderer: &dyn Renderer,
+    input: wgpu::TextureView,
+    double_thresholding: wgpu::TextureView,
+    edges: wgpu::TextureView,
+    params: &OverlayParams,
+) -> Result<wgpu::Texture, CannyError> {
+    const WORKGROUP_SIZE: u32 = 16;
+
+    let device = renderer.device();
+
+    let in_texture = input.texture();
+    validate_input_texture(in_texture)?;
+
+    let input_format_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Overlay: Input format buffer"),
+        contents: bytemuck::bytes_of(&GpuInputFormat::new(in_texture.format())),
+        usage: wgpu::BufferUsages::UNIFORM,
+    });
+
+    let params_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Overlay: Params buffer"),
+        contents: bytemuck::bytes_of(&GpuOverlayParams::from(params)),
+        usage: wgpu::BufferUsages::UNIFORM,
+    });
+
+    let out_texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Overlay: Texture"),
+        size: in_texture.size(),
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: in_texture.dimension(),
+        format: wgpu::TextureFormat::Rgba8Unorm,
+        usage: wgpu::TextureUsages::STORAGE_BINDING
+            | wgpu::TextureUsages::COPY_SRC
+            | wgpu::TextureUsages::TEXTURE_BINDING,
+        view_formats: &[],
+    });
+
+    let pipeline = Pipelines::overlay(renderer);
+
+    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Overlay: Bind group"),
+        layout: &pipeline.get_bind_group_layout(0),
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&input),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::TextureView(&double_thresholding),
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: wgpu::BindingResource::TextureView(&edges),
+            },
+            wgpu::BindGroupEntry {
+                binding: 3,
+                resource: wgpu::BindingResource::TextureView(
+                    &out_texture.create_view(&wgpu::TextureViewDescriptor::default()),
+                ),
+            },
+            wgpu::BindGroupEntry {
+                binding: 4,
+                resource: input_format_buffer.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 5,
+                resource: params_buffer.as_entire_binding(),
+            },
+        ],
+    });
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Overlay: Command encoder"),
+    });
+
+    {
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Overlay: Compute pass"),
+            timestamp_writes: crate::profile::timestamp_writes(renderer, "overlay"),
+        });
+
+        pass.set_bind_group(0, &bind_group, &[]);
+        pass.set_pipeline(&pipeline);
+        pass.dispatch_workgroups(
+            in_texture.width().div_ceil(WORKGROUP_SIZE),
+            in_texture.height().div_ceil(WORKGROUP_SIZE),
+            1,
+        );
+    }
+
+    crate::profile::submit(renderer, "overlay", encoder);
+
+    Ok(out_texture)
+}
@@ -0,0 +1,80 @@
+mod common;
+
+use image::DynamicImage;
+use pollster::FutureExt;
+use std::{
+    pin::pin,
+    task::{Context, Waker},
+};
+use wgpu_canny_edge_detection::{
+    CannyParams, DevicePoller, Renderer, apply_grayscale, canny_image_with_renderer,
+    canny_image_with_renderer_async, create_rgba_texture, read_texture, read_texture_async,
+    synthetic,
+};
+
+fn assert_send<T: Send>(_: &T) {}
+
+#[test]
+fn async_matches_blocking() {
+    let renderer = renderer_or_skip!();
+    let _poller = DevicePoller::new(renderer.device().clone());
+
+    let img = DynamicImage::ImageRgba8(synthetic::circle(96, 96, [48.2, 47.7], 24.3).image);
+    let params = CannyParams::default();
+
+    let future = canny_image_with_renderer_async(&renderer, &img, &params);
+    assert_send(&future);
+
+    assert_eq!(
+        future.block_on().unwrap(),
+        canny_image_with_renderer(&renderer, &img, &params).unwrap()
+    );
+}
+
+#[test]
+fn async_doesnt_wait_for_the_gpu() {
+    let renderer = renderer_or_skip!();
+
+    let img = DynamicImage::ImageRgba8(synthetic::circle(96, 96, [48.2, 47.7], 24.3).image);
+    let params = CannyParams::default();
+
+    // nobody polls the device yet, so any wait for the GPU while encoding the stages (like a
+    // readback of the edge tracking) would block or, in debug builds, panic
+    let mut future = pin!(canny_image_with_renderer_async(&renderer, &img, &params));
+    let mut context = Context::from_waker(Waker::noop());
+    assert!(future.as_mut().poll(&mut context).is_pending());
+
+    let _poller = DevicePoller::new(renderer.device().clone());
+    assert_eq!(
+        future.block_on().unwrap(),
+        canny_image_with_renderer(&renderer, &img, &params).unwrap()
+    );
+}
+
+#[test]
+fn concurrent_readbacks() {
+    let renderer = renderer_or_skip!();
+    let _poller = DevicePoller::new(renderer.device().clone());
+
+    let textures: Vec<_> = (0..4u8)
+        .map(|seed| {
+            let img = synthetic::text(64, 64, 20, seed as u64);
+            let input = create_rgba_texture(&renderer, &img.image);
+            apply_grayscale(&renderer, common::view(&input), None).unwrap()
+        })
+        .collect();
+
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = textures
+            .iter()
+            .map(|texture| {
+                let renderer = &renderer;
+                scope.spawn(move || read_texture_async(renderer, texture).block_on())
+            })
+            .collect();
+
+        for (handle, texture) in handles.into_iter().zip(&textures) {
+            assert_eq!(handle.join().unwrap(), read_texture(&renderer, texture));
+        }
+    });
+}
@@ -0,0 +1,49 @@
+mod common;
+
+use image::DynamicImage;
+use wgpu_canny_edge_detection::{
+    CannyError, CannyParams, canny_image, canny_image_with_renderer, metrics, synthetic,
+};
+
+#[test]
+fn edges_are_binary() {
+    let renderer = renderer_or_skip!();
+    let img = synthetic::circle(96, 96, [48.2, 47.7], 24.3);
+
+    let edges = canny_image_with_renderer(
+        &renderer,
+        &DynamicImage::ImageRgba8(img.image),
+        &CannyParams::default(),
+    )
+    .unwrap();
+
+    assert_eq!(edges.dimensions(), (96, 96));
+    assert!(edges.pixels().all(|pixel| [0, 255].contains(&pixel.0[0])));
+
+    let metrics = metrics::evaluate(&edges, &img.edges, 1.5);
+    assert!(metrics.recall > 0.99, "recall {:.3}", metrics.recall);
+}
+
+#[test]
+fn empty_image() {
+    let renderer = renderer_or_skip!();
+
+    let result = canny_image_with_renderer(
+        &renderer,
+        &DynamicImage::new_luma8(0, 0),
+        &CannyParams::default(),
+    );
+
+    assert_eq!(result, Err(CannyError::EmptyImage));
+}
+
+#[test]
+fn shared_renderer() {
+    let img = DynamicImage::ImageRgba8(synthetic::step_edge(64, 64, [32.3, 31.6], 0.3).image);
+
+    match canny_image(&img, &CannyParams::default()) {
+        Ok(edges) => assert!(edges.pixels().any(|pixel| pixel.0[0] == 255)),
+        Err(CannyError::NoAdapter(_)) => eprintln!("No adapter found, skipping the test"),
+        Err(err) => panic!("{err}"),
+    }
+}
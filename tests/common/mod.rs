@@ -0,0 +1,81 @@
+#![allow(dead_code)]
+
+use pollster::FutureExt;
+use wgpu_canny_edge_detection::{Pipelines, Profiler, Renderer};
+
+pub struct TestRenderer {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    pipelines: Pipelines,
+    pub profiler: Option<Profiler>,
+}
+
+impl TestRenderer {
+    /// Returns a renderer on a software adapter (lavapipe, llvmpipe, ...) or `None` if there's none.
+    pub fn new() -> Option<Self> {
+        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
+            backends: wgpu::Backends::VULKAN | wgpu::Backends::GL,
+            ..wgpu::InstanceDescriptor::from_env_or_default()
+        });
+
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                force_fallback_adapter: true,
+                ..Default::default()
+            })
+            .block_on()
+            .ok()?;
+
+        let (device, queue) = adapter
+            .request_device(&wgpu::DeviceDescriptor {
+                required_features: adapter.features()
+                    & (wgpu::Features::TIMESTAMP_QUERY | wgpu::Features::TEXTURE_FORMAT_16BIT_NORM),
+                ..Default::default()
+            })
+            .block_on()
+            .ok()?;
+
+        Some(Self {
+            device,
+            queue,
+            pipelines: Pipelines::default(),
+            profiler: None,
+        })
+    }
+}
+
+impl Renderer for TestRenderer {
+    fn device(&self) -> &wgpu::Device {
+        &self.device
+    }
+
+    fn queue(&self) -> &wgpu::Queue {
+        &self.queue
+    }
+
+    fn pipelines(&self) -> Option<&Pipelines> {
+        Some(&self.pipelines)
+    }
+
+    fn profiler(&self) -> Option<&Profiler> {
+        self.profiler.as_ref()
+    }
+}
+
+/// Returns a renderer or skips the test (by returning) if there's no software adapter.
+#[macro_export]
+macro_rules! renderer_or_skip {
+    () => {
+        match common::TestRenderer::new() {
+            Some(renderer) => renderer,
+            None => {
+                eprintln!("No software adapter found, skipping the test");
+                return;
+            }
+        }
+    };
+}
+
+pub fn view(texture: &wgpu::Texture) -> wgpu::TextureView {
+    texture.create_view(&wgpu::TextureViewDescriptor::default())
+}
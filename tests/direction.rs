@@ -0,0 +1,62 @@
+mod common;
+
+use common::view;
+use wgpu_canny_edge_detection::{
+    GradientOperator, apply_direction_visualization, apply_magnitude_and_angle,
+    apply_sobel_operators,
+    cpu::{self, GrayF32Image},
+    create_gray_texture, direction_legend, read_rgba_image, read_texture, synthetic,
+};
+
+#[test]
+fn direction_matches_cpu() {
+    let renderer = renderer_or_skip!();
+
+    let img = synthetic::circle(64, 48, [31.3, 23.7], 15.);
+    let gray = GrayF32Image::from_fn(64, 48, |x, y| {
+        image::Luma([img.image.get_pixel(x, y).0[0] as f32 / 255.])
+    });
+    let (horizontal, vertical) = apply_sobel_operators(
+        &renderer,
+        view(&create_gray_texture(&renderer, &gray)),
+        GradientOperator::Sobel,
+        None,
+    );
+    let (magnitudes, radians) =
+        apply_magnitude_and_angle(&renderer, view(&vertical), view(&horizontal), None);
+
+    let gpu = read_rgba_image(
+        &renderer,
+        &apply_direction_visualization(&renderer, view(&magnitudes), view(&radians)),
+    );
+
+    let to_image = |texture: &wgpu::Texture| {
+        GrayF32Image::from_raw(64, 48, read_texture(&renderer, texture)).unwrap()
+    };
+    let cpu = cpu::apply_direction_visualization(&to_image(&magnitudes), &to_image(&radians));
+
+    for (gpu, cpu) in gpu.pixels().zip(cpu.pixels()) {
+        for (gpu, cpu) in gpu.0.iter().zip(cpu.0) {
+            assert!(gpu.abs_diff(cpu) <= 1, "GPU {gpu:?} vs CPU {cpu:?}");
+        }
+    }
+}
+
+fn assert_close(actual: image::Rgba<u8>, expected: [u8; 4]) {
+    for (actual, expected) in actual.0.iter().zip(expected) {
+        assert!(actual.abs_diff(expected) <= 2, "{actual} vs {expected}");
+    }
+}
+
+#[test]
+fn legend() {
+    let legend = direction_legend(101);
+
+    // no gradient in the centre, (almost) the full magnitude at the border
+    assert_eq!(legend.get_pixel(50, 50).0, [0, 0, 0, 255]);
+    // a gradient to the right is red, one downwards (positive y) yellowish green
+    assert_close(*legend.get_pixel(100, 50), [255, 0, 0, 255]);
+    assert_close(*legend.get_pixel(50, 100), [128, 255, 0, 255]);
+    // outside of the wheel
+    assert_eq!(legend.get_pixel(0, 0).0, [0; 4]);
+}
@@ -0,0 +1,81 @@
+mod common;
+
+use common::view;
+use wgpu_canny_edge_detection::{
+    apply_edge_tracking, cpu::GrayF32Image, create_gray_texture, read_texture,
+};
+
+const SIZE: u32 = 64;
+const WEAK: f32 = 0.5;
+
+/// The result of `apply_edge_tracking` on the GPU and the CPU.
+fn track_edges(renderer: &common::TestRenderer, img: &GrayF32Image) -> (Vec<f32>, Vec<f32>) {
+    let texture = create_gray_texture(renderer, img);
+    let gpu = read_texture(
+        renderer,
+        &apply_edge_tracking(renderer, view(&texture), None),
+    );
+    let cpu = wgpu_canny_edge_detection::cpu::apply_edge_tracking(img, None);
+
+    (gpu, cpu.into_raw())
+}
+
+#[test]
+fn follows_long_chains_of_weak_edges() {
+    let renderer = renderer_or_skip!();
+
+    // a chain of weak edges through every tile of 16x16 pixels: along a row of each tile row, and
+    // from its end down to the next one
+    let mut img = GrayF32Image::new(SIZE, SIZE);
+    for row in 0..4 {
+        let y = row * 16 + 8;
+        for x in 0..SIZE {
+            img.put_pixel(x, y, image::Luma([WEAK]));
+        }
+
+        let turn_x = if row % 2 == 0 { SIZE - 1 } else { 0 };
+        for dy in 1..16 {
+            if y + dy < SIZE {
+                img.put_pixel(turn_x, y + dy, image::Luma([WEAK]));
+            }
+        }
+    }
+    // the only strong edge is at the end of the chain
+    img.put_pixel(SIZE - 1, 56, image::Luma([1.]));
+
+    let (gpu, cpu) = track_edges(&renderer, &img);
+
+    assert!(cpu.iter().all(|value| *value != WEAK));
+    assert_eq!(gpu, cpu);
+}
+
+#[test]
+fn cuts_chains_which_need_more_rounds() {
+    let renderer = renderer_or_skip!();
+
+    // a serpentine of weak edges through every other row, which crosses the tiles far more often
+    // than there are rounds
+    let mut img = GrayF32Image::new(SIZE, SIZE);
+    for y in (0..SIZE).step_by(2) {
+        for x in 0..SIZE {
+            img.put_pixel(x, y, image::Luma([WEAK]));
+        }
+
+        let turn_x = if y % 4 == 0 { SIZE - 1 } else { 0 };
+        if y + 1 < SIZE {
+            img.put_pixel(turn_x, y + 1, image::Luma([WEAK]));
+        }
+    }
+    // the only strong edge is at the end of the serpentine
+    img.put_pixel(0, SIZE - 2, image::Luma([1.]));
+
+    let (gpu, cpu) = track_edges(&renderer, &img);
+
+    // working in place, the GPU may get further in each round
+    assert!(cpu.contains(&WEAK));
+    assert!(
+        cpu.iter()
+            .zip(&gpu)
+            .all(|(cpu, gpu)| *cpu != 1. || *gpu == 1.)
+    );
+}
@@ -0,0 +1,85 @@
+mod common;
+
+use common::view;
+use wgpu_canny_edge_detection::{
+    OutputEncoding, TransferFunction, apply_output_encoding,
+    cpu::{self, GrayF32Image},
+    create_gray_texture, read_texture,
+};
+
+/// Signed values like the ones of the gradients.
+fn signed_ramp() -> GrayF32Image {
+    GrayF32Image::from_fn(37, 21, |x, y| {
+        image::Luma([(x as f32 - 18.) / 12. + (y as f32 * 0.7).sin() * 0.25])
+    })
+}
+
+#[test]
+fn encodings_match_cpu() {
+    let renderer = renderer_or_skip!();
+
+    let img = signed_ramp();
+    let texture = create_gray_texture(&renderer, &img);
+
+    for transfer in [
+        TransferFunction::Linear,
+        TransferFunction::Gamma,
+        TransferFunction::Srgb,
+    ] {
+        for flags in 0..8 {
+            let encoding = OutputEncoding {
+                transfer,
+                normalize: flags & 1 != 0,
+                absolute: flags & 2 != 0,
+                invert: flags & 4 != 0,
+            };
+
+            let encoded = apply_output_encoding(&renderer, view(&texture), &encoding);
+            let gpu = read_texture(&renderer, &encoded);
+            let cpu = cpu::apply_output_encoding(&img, &encoding);
+
+            for (gpu, cpu) in gpu.iter().zip(cpu.as_raw()) {
+                assert!(
+                    (gpu - cpu).abs() < 1e-5,
+                    "{encoding:?}: GPU {gpu} vs CPU {cpu}"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn normalize_stretches_absolute_values() {
+    let renderer = renderer_or_skip!();
+
+    let img = GrayF32Image::from_raw(4, 1, vec![-0.5, -0.25, 0.125, 0.25]).unwrap();
+    let texture = create_gray_texture(&renderer, &img);
+
+    let encoding = OutputEncoding {
+        normalize: true,
+        absolute: true,
+        ..Default::default()
+    };
+    let encoded = apply_output_encoding(&renderer, view(&texture), &encoding);
+
+    let values = read_texture(&renderer, &encoded);
+    for (value, expected) in values.iter().zip([1., 1. / 3., 0., 1. / 3.]) {
+        assert!((value - expected).abs() < 1e-6, "{values:?}");
+    }
+}
+
+#[test]
+fn inverted_edges() {
+    let renderer = renderer_or_skip!();
+
+    let img = GrayF32Image::from_raw(3, 1, vec![0., 0.5, 1.]).unwrap();
+    let texture = create_gray_texture(&renderer, &img);
+
+    let encoding = OutputEncoding {
+        invert: true,
+        ..Default::default()
+    };
+    let encoded = apply_output_encoding(&renderer, view(&texture), &encoding);
+
+    assert_eq!(read_texture(&renderer, &encoded), [1., 0.5, 0.]);
+}
@@ -0,0 +1,142 @@
+//! Compares each stage of the default parameters with the images in `example-images/`.
+mod common;
+
+use common::view;
+use image::GrayImage;
+use wgpu_canny_edge_detection::{
+    CannyParams, EdgeMasks, apply_double_thresholding, apply_edge_tracking, apply_gaussian_filter,
+    apply_grayscale, apply_magnitude_and_angle, apply_non_maximum_suppression,
+    apply_sobel_operators, cpu, create_rgba_texture, read_luma_image,
+};
+
+/// Pixels whose value differs by more than this are counted as mismatches.
+const MAX_PIXEL_DIFFERENCE: u8 = 2;
+
+/// The goldens were made on another GPU (and JPEG decoder), so a few pixels are allowed to differ.
+const CONTINUOUS_STAGE_MISMATCHES: f64 = 0.0001;
+/// A small difference in the input can flip a pixel of these stages from zero to one.
+const DISCRETE_STAGE_MISMATCHES: f64 = 0.001;
+
+fn example_image(name: &str) -> image::DynamicImage {
+    let path = format!("{}/example-images/{name}", env!("CARGO_MANIFEST_DIR"));
+    image::open(&path).unwrap_or_else(|err| panic!("Open {path}: {err}"))
+}
+
+/// Panics if more than `max_mismatches` (fraction) of the pixels differ from the golden image.
+fn assert_matches_golden(stage: &str, actual: &GrayImage, max_mismatches: f64) {
+    let golden = example_image(&format!("{stage}.png")).to_luma8();
+    assert_eq!(actual.dimensions(), golden.dimensions(), "{stage}");
+
+    let mismatches = actual
+        .pixels()
+        .zip(golden.pixels())
+        .filter(|(actual, golden)| actual.0[0].abs_diff(golden.0[0]) > MAX_PIXEL_DIFFERENCE)
+        .count();
+    let fraction = mismatches as f64 / actual.len() as f64;
+
+    assert!(
+        fraction <= max_mismatches,
+        "{stage}: {:.3}% of the pixels differ from the golden image, at most {:.3}% are allowed",
+        fraction * 100.,
+        max_mismatches * 100.
+    );
+}
+
+#[test]
+fn stages_match_golden_images() {
+    let renderer = renderer_or_skip!();
+    let params = CannyParams::default();
+    let castle = example_image("castle.jpg").to_rgba8();
+
+    let input = create_rgba_texture(&renderer, &castle);
+
+    let gray_scale = apply_grayscale(&renderer, view(&input), None).unwrap();
+    assert_matches_golden(
+        "1_gray_scale",
+        &read_luma_image(&renderer, &gray_scale),
+        CONTINUOUS_STAGE_MISMATCHES,
+    );
+
+    let gaussian = apply_gaussian_filter(
+        &renderer,
+        view(&gray_scale),
+        params.sigma,
+        params.kernel_radius,
+        None,
+    );
+    assert_matches_golden(
+        "2_gaussian",
+        &read_luma_image(&renderer, &gaussian),
+        CONTINUOUS_STAGE_MISMATCHES,
+    );
+
+    let (horizontal, vertical) =
+        apply_sobel_operators(&renderer, view(&gaussian), params.gradient_operator, None);
+    assert_matches_golden(
+        "3_horizontal",
+        &read_luma_image(&renderer, &horizontal),
+        CONTINUOUS_STAGE_MISMATCHES,
+    );
+    assert_matches_golden(
+        "3_vertical",
+        &read_luma_image(&renderer, &vertical),
+        CONTINUOUS_STAGE_MISMATCHES,
+    );
+
+    let (magnitudes, radians) =
+        apply_magnitude_and_angle(&renderer, view(&vertical), view(&horizontal), None);
+    assert_matches_golden(
+        "4_magnitude",
+        &read_luma_image(&renderer, &magnitudes),
+        CONTINUOUS_STAGE_MISMATCHES,
+    );
+    assert_matches_golden(
+        "4_radians",
+        &read_luma_image(&renderer, &radians),
+        DISCRETE_STAGE_MISMATCHES,
+    );
+
+    let non_maximum_suppression = apply_non_maximum_suppression(
+        &renderer,
+        view(&magnitudes),
+        view(&radians),
+        params.nms_mode,
+        None,
+    );
+    assert_matches_golden(
+        "5_non_maximum_suppression",
+        &read_luma_image(&renderer, &non_maximum_suppression),
+        DISCRETE_STAGE_MISMATCHES,
+    );
+
+    let threshold_texture = apply_double_thresholding(
+        &renderer,
+        view(&non_maximum_suppression),
+        params.thresholds,
+        None,
+        &EdgeMasks::default(),
+        None,
+    );
+    assert_matches_golden(
+        "6_threshold_texture",
+        &read_luma_image(&renderer, &threshold_texture),
+        DISCRETE_STAGE_MISMATCHES,
+    );
+
+    let edge_tracking = apply_edge_tracking(&renderer, view(&threshold_texture), None);
+    assert_matches_golden(
+        "7_edge_tracking",
+        &read_luma_image(&renderer, &edge_tracking),
+        DISCRETE_STAGE_MISMATCHES,
+    );
+}
+
+#[test]
+fn gpu_matches_cpu_reference() {
+    let renderer = renderer_or_skip!();
+    let castle = example_image("castle.jpg").to_rgba8();
+
+    for error in cpu::compare_with_gpu(&renderer, &castle, &CannyParams::default()) {
+        assert!(error.max_abs_error < 1e-4, "{error:?}");
+    }
+}
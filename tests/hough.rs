@@ -0,0 +1,238 @@
+mod common;
+
+use common::view;
+use wgpu_canny_edge_detection::{
+    CannyError, CannyParams, HoughCircle, HoughCirclesParams, HoughLineSegmentsParams,
+    HoughLinesParams, apply_gaussian_filter, apply_grayscale, apply_hough_circles,
+    apply_hough_line_segments, apply_hough_lines, apply_magnitude_and_angle, apply_sobel_operators,
+    cpu::GrayF32Image,
+    create_gray_texture, create_rgba_texture,
+    synthetic::{self, SyntheticImage},
+};
+
+const SIZE: u32 = 64;
+
+/// An edge map with the given edge pixels.
+fn edges(pixels: impl IntoIterator<Item = (u32, u32)>) -> GrayF32Image {
+    let mut img = GrayF32Image::new(SIZE, SIZE);
+    for (x, y) in pixels {
+        img.put_pixel(x, y, image::Luma([1.]));
+    }
+
+    img
+}
+
+#[test]
+fn horizontal_line() {
+    let renderer = renderer_or_skip!();
+
+    let texture = create_gray_texture(&renderer, &edges((0..SIZE).map(|x| (x, 20))));
+    let lines = apply_hough_lines(&renderer, view(&texture), &HoughLinesParams::default()).unwrap();
+
+    assert_eq!(lines.len(), 1, "{lines:?}");
+    assert!((lines[0].rho - 20.).abs() <= 1.);
+    assert!((lines[0].theta - std::f32::consts::FRAC_PI_2).abs() < 0.02);
+    assert_eq!(lines[0].votes, SIZE);
+}
+
+#[test]
+fn vertical_line() {
+    let renderer = renderer_or_skip!();
+
+    let texture = create_gray_texture(&renderer, &edges((0..SIZE).map(|y| (30, y))));
+    let lines = apply_hough_lines(&renderer, view(&texture), &HoughLinesParams::default()).unwrap();
+
+    assert_eq!(lines.len(), 1, "{lines:?}");
+    assert!((lines[0].rho - 30.).abs() <= 1.);
+    assert!(lines[0].theta < 0.02);
+}
+
+#[test]
+fn tilted_vertical_line_has_one_peak() {
+    let renderer = renderer_or_skip!();
+
+    // its normal lies just below pi, so it also gets many votes at a theta of 0
+    let pixels = (0..SIZE).map(|y| ((30. + y as f32 * 0.02).round() as u32, y));
+    let texture = create_gray_texture(&renderer, &edges(pixels));
+    let lines = apply_hough_lines(
+        &renderer,
+        view(&texture),
+        &HoughLinesParams {
+            threshold: 20,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    // the strongest line lies next to pi, and its neighbour across the wrap around isn't a peak
+    assert!(lines[0].theta > std::f32::consts::PI - 0.03, "{lines:?}");
+    assert!(lines.iter().all(|line| line.theta > 0.03), "{lines:?}");
+}
+
+#[test]
+fn line_segment() {
+    let renderer = renderer_or_skip!();
+
+    // a gap of 3 pixels is bridged
+    let pixels = (10..=50).filter(|x| !(30..33).contains(x)).map(|x| (x, 40));
+    let texture = create_gray_texture(&renderer, &edges(pixels));
+    let segments = apply_hough_line_segments(
+        &renderer,
+        view(&texture),
+        &HoughLineSegmentsParams {
+            lines: HoughLinesParams {
+                threshold: 20,
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    assert_eq!(segments.len(), 1, "{segments:?}");
+    assert_eq!(segments[0].start, [10, 40]);
+    assert_eq!(segments[0].end, [50, 40]);
+}
+
+/// The circles found in the gradients of `img`.
+fn find_circles(
+    renderer: &common::TestRenderer,
+    img: &SyntheticImage,
+    params: &HoughCirclesParams,
+) -> Result<Vec<HoughCircle>, CannyError> {
+    let canny = CannyParams::default();
+
+    let input = create_rgba_texture(renderer, &img.image);
+    let gray_scale = apply_grayscale(renderer, view(&input), None).unwrap();
+    let gaussian = apply_gaussian_filter(
+        renderer,
+        view(&gray_scale),
+        canny.sigma,
+        canny.kernel_radius,
+        None,
+    );
+    let (horizontal, vertical) =
+        apply_sobel_operators(renderer, view(&gaussian), canny.gradient_operator, None);
+    let (magnitudes, radians) =
+        apply_magnitude_and_angle(renderer, view(&vertical), view(&horizontal), None);
+
+    apply_hough_circles(renderer, view(&magnitudes), view(&radians), params)
+}
+
+/// Panics if the centre is more than `tolerance` or the radius more than one pixel off.
+fn assert_circle(circle: &HoughCircle, centre: [u32; 2], radius: u32, tolerance: u32) {
+    assert!(
+        circle.centre[0].abs_diff(centre[0]) <= tolerance
+            && circle.centre[1].abs_diff(centre[1]) <= tolerance,
+        "{circle:?}"
+    );
+    assert!(circle.radius.abs_diff(radius) <= 1, "{circle:?}");
+}
+
+#[test]
+fn circle() {
+    let renderer = renderer_or_skip!();
+
+    // the pixel (40, 32) covers [40, 41) x [32, 33)
+    let img = synthetic::circle(96, 80, [40.5, 32.5], 15.);
+    let circles = find_circles(&renderer, &img, &HoughCirclesParams::default()).unwrap();
+
+    assert_eq!(circles.len(), 1, "{circles:?}");
+    assert_circle(&circles[0], [40, 32], 15, 1);
+}
+
+#[test]
+fn circle_in_noise() {
+    let renderer = renderer_or_skip!();
+
+    let img = synthetic::circle(96, 80, [40.5, 32.5], 15.).with_noise(1.5, 7);
+    let circles = find_circles(
+        &renderer,
+        &img,
+        &HoughCirclesParams {
+            min_magnitude: 0.1,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    // the noise moves the centre, but the radius comes only from pixels which point at it
+    assert!(!circles.is_empty());
+    assert_circle(&circles[0], [40, 32], 15, 3);
+}
+
+#[test]
+fn two_circles() {
+    let renderer = renderer_or_skip!();
+
+    let img = synthetic::pattern(128, 96, |x, y| {
+        (x - 30.5).hypot(y - 48.5) < 12. || (x - 80.5).hypot(y - 48.5) < 30.
+    });
+    let circles = find_circles(
+        &renderer,
+        &img,
+        &HoughCirclesParams {
+            threshold: 100,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    assert_eq!(circles.len(), 2, "{circles:?}");
+    let (small, big) = if circles[0].radius < circles[1].radius {
+        (&circles[0], &circles[1])
+    } else {
+        (&circles[1], &circles[0])
+    };
+    assert_circle(small, [30, 48], 12, 1);
+    assert_circle(big, [80, 48], 30, 1);
+}
+
+#[test]
+fn circles_rejects_min_radius_above_max_radius() {
+    let renderer = renderer_or_skip!();
+
+    let img = synthetic::circle(64, 64, [32., 32.], 10.);
+    let result = find_circles(
+        &renderer,
+        &img,
+        &HoughCirclesParams {
+            min_radius: 20,
+            max_radius: 10,
+            ..Default::default()
+        },
+    );
+
+    assert!(
+        matches!(result, Err(CannyError::InvalidParams(_))),
+        "{result:?}"
+    );
+}
+
+#[test]
+fn lines_reject_invalid_resolutions() {
+    let renderer = renderer_or_skip!();
+
+    let texture = create_gray_texture(&renderer, &edges((0..SIZE).map(|x| (x, 20))));
+    for (rho_resolution, theta_resolution) in [
+        (0., 0.01),
+        (-1., 0.01),
+        (f32::NAN, 0.01),
+        (1., 0.),
+        (1., -0.01),
+        (1., f32::INFINITY),
+        (1e-6, 1e-6),
+    ] {
+        let params = HoughLinesParams {
+            rho_resolution,
+            theta_resolution,
+            ..Default::default()
+        };
+        let result = apply_hough_lines(&renderer, view(&texture), &params);
+
+        assert!(
+            matches!(result, Err(CannyError::InvalidParams(_))),
+            "{params:?}: {result:?}"
+        );
+    }
+}
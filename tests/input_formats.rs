@@ -0,0 +1,282 @@
+mod common;
+
+use common::{TestRenderer, view};
+use image::{DynamicImage, GrayImage, ImageBuffer, Luma, Rgb, Rgb32FImage, Rgba, RgbaImage};
+use wgpu_canny_edge_detection::{
+    CannyError, CannyParams, EdgeMasks, Renderer, apply_canny, apply_grayscale,
+    create_image_texture, create_rgba_texture, read_texture, validate_input_texture,
+};
+
+const SIZE: u32 = 48;
+
+/// The sRGB textures are decoded on load and encoded again by the gray scaling, which may be off
+/// by up to half a gray level.
+const SRGB_TOLERANCE: f32 = 0.5 / 255.;
+
+fn create_texture(
+    renderer: &TestRenderer,
+    format: wgpu::TextureFormat,
+    usage: wgpu::TextureUsages,
+    bytes: &[u8],
+) -> wgpu::Texture {
+    let texture = renderer.device().create_texture(&wgpu::TextureDescriptor {
+        label: Some("Test input texture"),
+        size: wgpu::Extent3d {
+            width: SIZE,
+            height: SIZE,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: usage | wgpu::TextureUsages::COPY_DST,
+        view_formats: &[],
+    });
+
+    renderer.queue().write_texture(
+        wgpu::TexelCopyTextureInfo {
+            texture: &texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d::ZERO,
+            aspect: wgpu::TextureAspect::All,
+        },
+        bytes,
+        wgpu::TexelCopyBufferLayout {
+            offset: 0,
+            bytes_per_row: Some(format.block_copy_size(None).unwrap() * SIZE),
+            rows_per_image: Some(SIZE),
+        },
+        texture.size(),
+    );
+
+    texture
+}
+
+fn colors() -> RgbaImage {
+    RgbaImage::from_fn(SIZE, SIZE, |x, y| {
+        Rgba([(x * 5) as u8, (y * 5) as u8, ((x + y) * 2) as u8, 255])
+    })
+}
+
+fn grays() -> GrayImage {
+    GrayImage::from_fn(SIZE, SIZE, |x, y| Luma([((x * 3 + y * 2) % 256) as u8]))
+}
+
+/// Converts a value in `[0, 1]` into the bits of a half float.
+fn f16_bits(value: f32) -> u16 {
+    if value == 0. {
+        return 0;
+    }
+
+    let bits = value.to_bits();
+    let exponent = ((bits >> 23) & 0xff) as i32 - 127 + 15;
+    let mantissa = (bits >> 13) & 0x3ff;
+    ((exponent as u32) << 10 | mantissa) as u16
+}
+
+fn assert_close(name: &str, actual: &[f32], expected: &[f32], tolerance: f32) {
+    let max_error = actual
+        .iter()
+        .zip(expected)
+        .map(|(a, b)| (a - b).abs())
+        .fold(0., f32::max);
+
+    assert!(
+        max_error <= tolerance,
+        "{name}: max error {max_error} (at most {tolerance})"
+    );
+}
+
+#[test]
+fn color_formats_match_rgba8() {
+    let renderer = renderer_or_skip!();
+    let colors = colors();
+    let usage = wgpu::TextureUsages::TEXTURE_BINDING;
+
+    let expected = read_texture(
+        &renderer,
+        &apply_grayscale(
+            &renderer,
+            view(&create_rgba_texture(&renderer, &colors)),
+            None,
+        )
+        .unwrap(),
+    );
+
+    let rgba: Vec<u8> = colors.as_raw().clone();
+    let bgra: Vec<u8> = colors
+        .pixels()
+        .flat_map(|Rgba([r, g, b, a])| [*b, *g, *r, *a])
+        .collect();
+    let rgba16: Vec<u8> = colors
+        .as_raw()
+        .iter()
+        .flat_map(|&value| f16_bits(value as f32 / 255.).to_le_bytes())
+        .collect();
+
+    for (format, bytes, tolerance) in [
+        (wgpu::TextureFormat::Rgba8UnormSrgb, &rgba, SRGB_TOLERANCE),
+        (wgpu::TextureFormat::Bgra8Unorm, &bgra, 1e-6),
+        (wgpu::TextureFormat::Bgra8UnormSrgb, &bgra, SRGB_TOLERANCE),
+        (wgpu::TextureFormat::Rgba16Float, &rgba16, 1e-3),
+    ] {
+        let input = create_texture(&renderer, format, usage, bytes);
+        let gray_scale = apply_grayscale(&renderer, view(&input), None).unwrap();
+
+        assert_close(
+            &format!("{format:?}"),
+            &read_texture(&renderer, &gray_scale),
+            &expected,
+            tolerance,
+        );
+    }
+}
+
+#[test]
+fn single_channel_formats_are_gray_values() {
+    let renderer = renderer_or_skip!();
+    let grays = grays();
+    let usage = wgpu::TextureUsages::TEXTURE_BINDING;
+
+    let expected: Vec<f32> = grays.as_raw().iter().map(|&v| v as f32 / 255.).collect();
+
+    let r8 = grays.as_raw().clone();
+    let r16: Vec<u8> = grays
+        .as_raw()
+        .iter()
+        .flat_map(|&value| (value as u16 * 257).to_le_bytes())
+        .collect();
+    let r32: Vec<u8> = bytemuck::cast_slice(&expected).to_vec();
+
+    let mut formats = vec![
+        (wgpu::TextureFormat::R8Unorm, &r8),
+        (wgpu::TextureFormat::R32Float, &r32),
+    ];
+    if renderer
+        .device()
+        .features()
+        .contains(wgpu::Features::TEXTURE_FORMAT_16BIT_NORM)
+    {
+        formats.push((wgpu::TextureFormat::R16Unorm, &r16));
+    }
+
+    for (format, bytes) in formats {
+        let input = create_texture(&renderer, format, usage, bytes);
+        let gray_scale = apply_grayscale(&renderer, view(&input), None).unwrap();
+
+        assert_close(
+            &format!("{format:?}"),
+            &read_texture(&renderer, &gray_scale),
+            &expected,
+            1e-6,
+        );
+    }
+}
+
+#[test]
+fn r32float_input_skips_the_gray_scaling() {
+    let renderer = renderer_or_skip!();
+    let grays = grays();
+    let usage = wgpu::TextureUsages::TEXTURE_BINDING;
+
+    let values: Vec<f32> = grays.as_raw().iter().map(|&v| v as f32 / 255.).collect();
+    let r8 = create_texture(
+        &renderer,
+        wgpu::TextureFormat::R8Unorm,
+        usage,
+        grays.as_raw(),
+    );
+    let r32 = create_texture(
+        &renderer,
+        wgpu::TextureFormat::R32Float,
+        usage,
+        bytemuck::cast_slice(&values),
+    );
+
+    let edges = |input: &wgpu::Texture| {
+        let edges = apply_canny(
+            &renderer,
+            view(input),
+            &CannyParams::default(),
+            None,
+            &EdgeMasks::default(),
+        )
+        .unwrap();
+        read_texture(&renderer, &edges)
+    };
+
+    assert_eq!(edges(&r32), edges(&r8));
+}
+
+#[test]
+fn invalid_inputs() {
+    let renderer = renderer_or_skip!();
+
+    let rg8 = create_texture(
+        &renderer,
+        wgpu::TextureFormat::Rg8Unorm,
+        wgpu::TextureUsages::TEXTURE_BINDING,
+        &vec![0; (SIZE * SIZE * 2) as usize],
+    );
+    assert_eq!(
+        validate_input_texture(&rg8),
+        Err(CannyError::UnsupportedFormat(wgpu::TextureFormat::Rg8Unorm))
+    );
+    assert!(matches!(
+        apply_grayscale(&renderer, view(&rg8), None),
+        Err(CannyError::UnsupportedFormat(wgpu::TextureFormat::Rg8Unorm))
+    ));
+
+    let not_bindable = create_texture(
+        &renderer,
+        wgpu::TextureFormat::Rgba8Unorm,
+        wgpu::TextureUsages::COPY_SRC,
+        &vec![0; (SIZE * SIZE * 4) as usize],
+    );
+    assert_eq!(
+        validate_input_texture(&not_bindable),
+        Err(CannyError::MissingUsage(
+            wgpu::TextureUsages::TEXTURE_BINDING
+        ))
+    );
+    assert!(matches!(
+        apply_canny(
+            &renderer,
+            view(&not_bindable),
+            &CannyParams::default(),
+            None,
+            &EdgeMasks::default(),
+        ),
+        Err(CannyError::MissingUsage(_))
+    ));
+}
+
+#[test]
+fn images_keep_their_precision() {
+    let renderer = renderer_or_skip!();
+
+    let luma16 =
+        ImageBuffer::<Luma<u16>, _>::from_fn(SIZE, SIZE, |x, y| Luma([(x * SIZE + y) as u16]));
+    let rgb32f = Rgb32FImage::from_fn(SIZE, SIZE, |x, y| {
+        Rgb([x as f32 / 1e4, y as f32 / 1e4, (x + y) as f32 / 1e4])
+    });
+
+    for (name, img) in [
+        ("Luma16", DynamicImage::ImageLuma16(luma16)),
+        ("Rgb32F", DynamicImage::ImageRgb32F(rgb32f)),
+    ] {
+        let input = create_image_texture(&renderer, &img);
+        let gray_scale = read_texture(
+            &renderer,
+            &apply_grayscale(&renderer, view(&input), None).unwrap(),
+        );
+
+        let expected: Vec<f32> = img
+            .to_rgba32f()
+            .pixels()
+            .map(|Rgba([r, g, b, _])| 0.3 * r + 0.59 * g + 0.11 * b)
+            .collect();
+        assert_close(name, &gray_scale, &expected, 1e-7);
+    }
+}
@@ -0,0 +1,164 @@
+mod common;
+
+use common::view;
+use image::{GrayImage, Luma};
+use wgpu::util::DeviceExt;
+use wgpu_canny_edge_detection::{
+    CannyParams, EdgeMasks, Renderer, Roi, Thresholds, apply_canny, apply_double_thresholding,
+    cpu::GrayF32Image, create_gray_texture, create_rgba_texture, read_texture, synthetic,
+};
+
+const SIZE: u32 = 16;
+/// The masks are bigger than the thresholded texture, which lies at this position inside of them.
+const ORIGIN: [u32; 2] = [5, 3];
+const MASK_SIZE: u32 = 24;
+
+/// Uploads `img` into a `R8Unorm` mask.
+fn create_mask(renderer: &dyn Renderer, img: &GrayImage) -> wgpu::Texture {
+    renderer.device().create_texture_with_data(
+        renderer.queue(),
+        &wgpu::TextureDescriptor {
+            label: Some("Mask texture"),
+            size: wgpu::Extent3d {
+                width: img.width(),
+                height: img.height(),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::R8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        },
+        wgpu::util::TextureDataOrder::LayerMajor,
+        img.as_raw(),
+    )
+}
+
+/// A mask of the given size which is `value` at `pixels` and `fill` everywhere else.
+fn mask(size: u32, fill: u8, pixels: &[(u32, u32)], value: u8) -> GrayImage {
+    let mut img = GrayImage::from_pixel(size, size, Luma([fill]));
+    for &(x, y) in pixels {
+        img.put_pixel(x, y, Luma([value]));
+    }
+
+    img
+}
+
+fn at(values: &[f32], x: u32, y: u32) -> f32 {
+    values[(y * SIZE + x) as usize]
+}
+
+#[test]
+fn ignored_pixels_are_no_edges_and_dont_set_the_max() {
+    let renderer = renderer_or_skip!();
+
+    // the strongest pixel is ignored, so the weaker one becomes a strong edge
+    let mut nms = GrayF32Image::new(SIZE, SIZE);
+    nms.put_pixel(2, 4, Luma([1.]));
+    nms.put_pixel(10, 10, Luma([0.15]));
+    let nms = create_gray_texture(&renderer, &nms);
+
+    // the mask is offset by the origin, a mask without it would ignore nothing
+    let ignore = create_mask(
+        &renderer,
+        &mask(MASK_SIZE, 255, &[(2 + ORIGIN[0], 4 + ORIGIN[1])], 0),
+    );
+    let masks = EdgeMasks {
+        ignore: Some(view(&ignore)),
+        origin: ORIGIN,
+        ..Default::default()
+    };
+
+    let values = read_texture(
+        &renderer,
+        &apply_double_thresholding(
+            &renderer,
+            view(&nms),
+            Thresholds::default(),
+            None,
+            &masks,
+            None,
+        ),
+    );
+
+    assert_eq!(at(&values, 2, 4), 0.);
+    assert_eq!(at(&values, 10, 10), 1.);
+}
+
+#[test]
+fn seeds_are_strong_edges() {
+    let renderer = renderer_or_skip!();
+
+    let mut nms = GrayF32Image::new(SIZE, SIZE);
+    nms.put_pixel(10, 10, Luma([1.]));
+    let nms = create_gray_texture(&renderer, &nms);
+
+    let seed = create_mask(
+        &renderer,
+        &mask(MASK_SIZE, 0, &[(3 + ORIGIN[0], 7 + ORIGIN[1])], 255),
+    );
+    let masks = EdgeMasks {
+        seed: Some(view(&seed)),
+        origin: ORIGIN,
+        ..Default::default()
+    };
+
+    let values = read_texture(
+        &renderer,
+        &apply_double_thresholding(
+            &renderer,
+            view(&nms),
+            Thresholds::default(),
+            None,
+            &masks,
+            None,
+        ),
+    );
+
+    assert_eq!(at(&values, 3, 7), 1.);
+    assert_eq!(at(&values, 10, 10), 1.);
+    let edges = values.iter().filter(|value| **value == 1.).count();
+    assert_eq!(edges, 2);
+}
+
+#[test]
+fn canny_with_roi_uses_masks_of_the_whole_image() {
+    let renderer = renderer_or_skip!();
+
+    let (width, height) = (96, 64);
+    let img = synthetic::circle(width, height, [48.5, 32.5], 14.);
+    let input = create_rgba_texture(&renderer, &img.image);
+
+    // ignores the left half of the image
+    let ignore = GrayImage::from_fn(width, height, |x, _| Luma([if x < 48 { 0 } else { 255 }]));
+    let ignore = create_mask(&renderer, &ignore);
+    let masks = EdgeMasks {
+        ignore: Some(view(&ignore)),
+        ..Default::default()
+    };
+
+    let roi = Roi::new(30, 10, 40, 44);
+    let edges = read_texture(
+        &renderer,
+        &apply_canny(
+            &renderer,
+            view(&input),
+            &CannyParams::default(),
+            Some(roi),
+            &masks,
+        )
+        .unwrap(),
+    );
+
+    for y in 0..roi.height {
+        for x in 0..roi.width {
+            let value = edges[(y * roi.width + x) as usize];
+            if roi.x + x < 48 {
+                assert_eq!(value, 0., "({x}, {y})");
+            }
+        }
+    }
+    assert!(edges.contains(&1.));
+}
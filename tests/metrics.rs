@@ -0,0 +1,71 @@
+use image::{GrayImage, Luma};
+use wgpu_canny_edge_detection::metrics::evaluate;
+
+fn vertical_line(x: u32) -> GrayImage {
+    GrayImage::from_fn(32, 32, |px, _| Luma([if px == x { 255 } else { 0 }]))
+}
+
+#[test]
+fn identical_edges_are_perfect() {
+    let edges = vertical_line(10);
+
+    let metrics = evaluate(&edges, &edges, 0.);
+
+    assert_eq!(metrics.pratt_fom, 1.);
+    assert_eq!(metrics.precision, 1.);
+    assert_eq!(metrics.recall, 1.);
+    assert_eq!(metrics.f1, 1.);
+    assert_eq!(metrics.hausdorff, 0.);
+}
+
+#[test]
+fn shifted_edges() {
+    let metrics = evaluate(&vertical_line(12), &vertical_line(10), 1.5);
+
+    assert!((metrics.pratt_fom - 1. / (1. + 4. / 9.)).abs() < 1e-6);
+    assert_eq!(metrics.precision, 0.);
+    assert_eq!(metrics.recall, 0.);
+    assert_eq!(metrics.f1, 0.);
+    assert_eq!(metrics.hausdorff, 2.);
+
+    let metrics = evaluate(&vertical_line(12), &vertical_line(10), 2.);
+    assert_eq!(metrics.f1, 1.);
+}
+
+#[test]
+fn missing_edges() {
+    let mut ground_truth = vertical_line(10);
+    for y in 0..32 {
+        ground_truth.put_pixel(20, y, Luma([255]));
+    }
+
+    let metrics = evaluate(&vertical_line(10), &ground_truth, 1.);
+
+    assert_eq!(metrics.precision, 1.);
+    assert_eq!(metrics.recall, 0.5);
+    assert!((metrics.f1 - 2. / 3.).abs() < 1e-6);
+    assert_eq!(metrics.pratt_fom, 0.5);
+    assert_eq!(metrics.hausdorff, 10.);
+}
+
+#[test]
+fn diagonal_distances_are_euclidean() {
+    let mut detected = GrayImage::new(32, 32);
+    detected.put_pixel(3, 4, Luma([255]));
+    let mut ground_truth = GrayImage::new(32, 32);
+    ground_truth.put_pixel(0, 0, Luma([255]));
+
+    let metrics = evaluate(&detected, &ground_truth, 1.);
+
+    assert_eq!(metrics.hausdorff, 5.);
+}
+
+#[test]
+fn no_detected_edges() {
+    let metrics = evaluate(&GrayImage::new(32, 32), &vertical_line(10), 1.);
+
+    assert_eq!(metrics.precision, 1.);
+    assert_eq!(metrics.recall, 0.);
+    assert_eq!(metrics.pratt_fom, 0.);
+    assert_eq!(metrics.hausdorff, f32::INFINITY);
+}
@@ -0,0 +1,38 @@
+use wgpu_canny_edge_detection::npy;
+
+fn header(bytes: &[u8]) -> &str {
+    let len = u16::from_le_bytes([bytes[8], bytes[9]]) as usize;
+    std::str::from_utf8(&bytes[10..10 + len]).unwrap()
+}
+
+#[test]
+fn header_and_values() {
+    let values = [0., 0.5, -1., 2.25, f32::MAX, 1e-3];
+
+    let mut bytes = Vec::new();
+    npy::write(&mut bytes, &values, 3, 2).unwrap();
+
+    assert_eq!(&bytes[..8], b"\x93NUMPY\x01\x00");
+    let header = header(&bytes);
+    assert_eq!(
+        header.trim_end(),
+        "{'descr': '<f4', 'fortran_order': False, 'shape': (2, 3), }"
+    );
+    assert!(header.ends_with('\n'));
+
+    let data_start = 10 + header.len();
+    assert_eq!(data_start % 64, 0);
+
+    let data: Vec<f32> = bytes[data_start..]
+        .chunks(4)
+        .map(|chunk| f32::from_le_bytes(chunk.try_into().unwrap()))
+        .collect();
+    assert_eq!(data, values);
+}
+
+#[test]
+fn wrong_amount_of_values() {
+    let result = npy::write(Vec::new(), &[0.; 5], 3, 2);
+
+    assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+}
@@ -0,0 +1,100 @@
+mod common;
+
+use common::view;
+use wgpu_canny_edge_detection::{
+    OverlayParams, Roi, apply_edge_tracking, apply_overlay,
+    cpu::{self, GrayF32Image},
+    create_gray_texture, create_rgba_texture, read_rgba_image, read_texture,
+};
+
+/// A strong edge at x = 1, a weak edge next to it at x = 2 and a lone weak edge at x = 5.
+fn thresholds() -> GrayF32Image {
+    GrayF32Image::from_fn(8, 3, |x, _| {
+        image::Luma([match x {
+            1 => 1.,
+            2 | 5 => 0.5,
+            _ => 0.,
+        }])
+    })
+}
+
+#[test]
+fn strong_and_weak_edges() {
+    let renderer = renderer_or_skip!();
+
+    let input = image::RgbaImage::from_pixel(8, 3, image::Rgba([10, 20, 30, 255]));
+    let input_texture = create_rgba_texture(&renderer, &input);
+    let thresholds = create_gray_texture(&renderer, &thresholds());
+    let edges = apply_edge_tracking(&renderer, view(&thresholds), Some(Roi::new(0, 0, 8, 3)));
+
+    let params = OverlayParams {
+        strong_color: [255, 0, 0, 255],
+        weak_color: [0, 0, 255, 128],
+        dilation: 0,
+    };
+    let overlay = read_rgba_image(
+        &renderer,
+        &apply_overlay(
+            &renderer,
+            view(&input_texture),
+            view(&thresholds),
+            view(&edges),
+            &params,
+        )
+        .unwrap(),
+    );
+
+    assert_eq!(overlay.get_pixel(0, 1).0, [10, 20, 30, 255]);
+    assert_eq!(overlay.get_pixel(1, 1).0, [255, 0, 0, 255]);
+    // blended with the input
+    assert_eq!(overlay.get_pixel(2, 1).0, [5, 10, 143, 255]);
+    // not kept by the edge tracking
+    assert_eq!(overlay.get_pixel(5, 1).0, [10, 20, 30, 255]);
+}
+
+#[test]
+fn overlay_matches_cpu() {
+    let renderer = renderer_or_skip!();
+
+    let input = image::RgbaImage::from_fn(8, 3, |x, y| {
+        image::Rgba([(x * 30) as u8, (y * 80) as u8, 200, 255])
+    });
+    let input_texture = create_rgba_texture(&renderer, &input);
+    let thresholds_texture = create_gray_texture(&renderer, &thresholds());
+    let edges = apply_edge_tracking(
+        &renderer,
+        view(&thresholds_texture),
+        Some(Roi::new(0, 0, 8, 3)),
+    );
+    let edges_image = GrayF32Image::from_raw(8, 3, read_texture(&renderer, &edges)).unwrap();
+
+    for dilation in 0..3 {
+        let params = OverlayParams {
+            dilation,
+            weak_color: [0, 255, 0, 100],
+            ..Default::default()
+        };
+
+        let gpu = read_rgba_image(
+            &renderer,
+            &apply_overlay(
+                &renderer,
+                view(&input_texture),
+                view(&thresholds_texture),
+                view(&edges),
+                &params,
+            )
+            .unwrap(),
+        );
+        let cpu = cpu::apply_overlay(&input, &thresholds(), &edges_image, &params);
+
+        for (gpu, cpu) in gpu.pixels().zip(cpu.pixels()) {
+            for (gpu, cpu) in gpu.0.iter().zip(cpu.0) {
+                assert!(
+                    gpu.abs_diff(cpu) <= 1,
+                    "dilation {dilation}: {gpu} vs {cpu}"
+                );
+            }
+        }
+    }
+}
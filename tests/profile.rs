@@ -0,0 +1,69 @@
+mod common;
+
+use std::time::{Duration, Instant};
+
+use common::view;
+use wgpu_canny_edge_detection::{
+    CannyParams, EdgeMasks, Profiler, Renderer, Timer, apply_canny, create_rgba_texture, synthetic,
+};
+
+#[test]
+fn profile_has_every_stage() {
+    let mut renderer = renderer_or_skip!();
+    renderer.profiler = Some(Profiler::new(renderer.device()));
+
+    let img = synthetic::circle(64, 64, [32.2, 31.7], 16.3);
+    let input = create_rgba_texture(&renderer, &img.image);
+    let start = Instant::now();
+    apply_canny(
+        &renderer,
+        view(&input),
+        &CannyParams::default(),
+        None,
+        &EdgeMasks::default(),
+    )
+    .unwrap();
+    renderer.device().poll(wgpu::PollType::Wait).unwrap();
+    let elapsed = start.elapsed();
+
+    let profiler = renderer.profiler.as_ref().unwrap();
+    let profile = profiler.finish(&renderer);
+
+    let expected_timer = if renderer
+        .device()
+        .features()
+        .contains(wgpu::Features::TIMESTAMP_QUERY)
+    {
+        Timer::GpuTimestamps
+    } else {
+        Timer::CpuWallClock
+    };
+    assert_eq!(profile.timer, expected_timer);
+
+    let stages: Vec<_> = profile.stages.iter().map(|stage| stage.stage).collect();
+    assert_eq!(
+        stages,
+        [
+            "gray scale",
+            "gaussian",
+            "gradients",
+            "magnitude",
+            "non maximum suppression",
+            "double thresholding",
+            "edge tracking",
+        ]
+    );
+    // the stages ran one after another while the time was measured
+    for stage in &profile.stages {
+        assert!(
+            stage.duration <= elapsed,
+            "{stage:?} took longer than the whole edge detection ({elapsed:?})"
+        );
+    }
+    // a single stage can be faster than the timer resolution, all of them together not
+    let total: Duration = profile.stages.iter().map(|stage| stage.duration).sum();
+    assert!(total > Duration::ZERO);
+
+    // finishing resets the profiler
+    assert!(profiler.finish(&renderer).stages.is_empty());
+}
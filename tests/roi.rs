@@ -0,0 +1,125 @@
+mod common;
+
+use common::view;
+use wgpu_canny_edge_detection::{
+    CannyError, CannyParams, EdgeMasks, ROI_EDGE_TRACKING_APRON, Roi, apply_canny,
+    create_rgba_texture, read_texture, synthetic,
+};
+
+const WIDTH: u32 = 128;
+const HEIGHT: u32 = 96;
+
+/// Asserts that the edges of the ROI are the same as the ones of the whole image there.
+fn assert_roi_matches_crop(renderer: &common::TestRenderer, img: &image::RgbaImage, roi: Roi) {
+    let params = CannyParams::default();
+    let input = create_rgba_texture(renderer, img);
+
+    let whole = read_texture(
+        renderer,
+        &apply_canny(renderer, view(&input), &params, None, &EdgeMasks::default()).unwrap(),
+    );
+    let cropped: Vec<f32> = (roi.y..roi.y + roi.height)
+        .flat_map(|y| (roi.x..roi.x + roi.width).map(move |x| (x, y)))
+        .map(|(x, y)| whole[(y * img.width() + x) as usize])
+        .collect();
+
+    let roi_texture = apply_canny(
+        renderer,
+        view(&input),
+        &params,
+        Some(roi),
+        &EdgeMasks::default(),
+    )
+    .unwrap();
+    assert_eq!(
+        (roi_texture.width(), roi_texture.height()),
+        (roi.width, roi.height)
+    );
+
+    let edges = read_texture(renderer, &roi_texture);
+    assert!(edges.contains(&1.));
+    assert_eq!(edges, cropped);
+}
+
+#[test]
+fn roi_matches_crop_of_whole_image() {
+    let renderer = renderer_or_skip!();
+
+    // the ROI cuts through the circle, which lies completely inside of the padded ROI so the
+    // strongest edge is the same
+    let img = synthetic::circle(WIDTH, HEIGHT, [40.3, 40.6], 12.);
+    assert_roi_matches_crop(&renderer, &img.image, Roi::new(36, 30, 40, 30));
+}
+
+#[test]
+fn roi_follows_weak_edges_through_the_apron() {
+    let renderer = renderer_or_skip!();
+
+    // a vertical edge which is strong above y = 40 and weak below, only the weak part lies inside
+    // of the ROI and the strong one inside of its apron
+    let img = image::RgbaImage::from_fn(WIDTH, HEIGHT, |x, y| match (x < 64, y < 40) {
+        (false, _) => image::Rgba([200, 200, 200, 255]),
+        (true, true) => image::Rgba([40, 40, 40, 255]),
+        (true, false) => image::Rgba([175, 175, 175, 255]),
+    });
+    let roi = Roi::new(50, 50, 30, 30);
+    assert!(roi.y - CannyParams::default().apron() - ROI_EDGE_TRACKING_APRON < 40);
+
+    assert_roi_matches_crop(&renderer, &img, roi);
+}
+
+#[test]
+fn validate() {
+    assert_eq!(Roi::new(10, 20, 30, 40).validate(40, 60), Ok(()));
+
+    for roi in [
+        Roi::new(10, 20, 0, 40),
+        Roi::new(10, 20, 31, 40),
+        Roi::new(10, 21, 30, 40),
+        Roi::new(u32::MAX, 0, 2, 1),
+    ] {
+        assert_eq!(
+            roi.validate(40, 60),
+            Err(CannyError::InvalidRoi {
+                roi,
+                width: 40,
+                height: 60
+            })
+        );
+    }
+}
+
+#[test]
+fn padded() {
+    assert_eq!(
+        Roi::new(2, 30, 10, 20).padded(5, 40, 52),
+        Roi::new(0, 25, 17, 27)
+    );
+    // doesn't overflow
+    assert_eq!(
+        Roi::new(u32::MAX - 1, 0, 1, 1).padded(5, 40, 52),
+        Roi::new(u32::MAX - 6, 0, 0, 6)
+    );
+}
+
+#[test]
+fn canny_rejects_roi_outside_of_the_texture() {
+    let renderer = renderer_or_skip!();
+
+    let img = synthetic::circle(WIDTH, HEIGHT, [40., 40.], 12.);
+    let input = create_rgba_texture(&renderer, &img.image);
+    let roi = Roi::new(100, 0, 40, 10);
+
+    let result = apply_canny(
+        &renderer,
+        view(&input),
+        &CannyParams::default(),
+        Some(roi),
+        &EdgeMasks::default(),
+    );
+
+    assert!(
+        matches!(result, Err(CannyError::InvalidRoi { roi: invalid, .. }) if invalid == roi),
+        "{result:?}"
+    );
+}
@@ -0,0 +1,64 @@
+use std::time::Duration;
+
+use image::{AnimationDecoder, Rgba, RgbaImage, codecs::gif::GifDecoder};
+use wgpu_canny_edge_detection::sheet::{self, LabelledImage};
+
+fn images() -> Vec<LabelledImage> {
+    (0..5)
+        .map(|index| {
+            let gray = index as u8 * 50;
+            LabelledImage::new(
+                format!("{index}_stage"),
+                RgbaImage::from_pixel(20, 10, Rgba([gray, gray, gray, 255])),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn grid() {
+    let images = images();
+    let sheet = sheet::contact_sheet(&images, 2);
+
+    let cell_height = 10 + sheet::label_height();
+    // 4 pixels of padding around the cells
+    assert_eq!(
+        sheet.dimensions(),
+        (2 * 20 + 3 * 4, 3 * cell_height + 4 * 4)
+    );
+
+    // the images are placed row by row below their labels
+    let image_y = 4 + sheet::label_height();
+    assert_eq!(
+        sheet.get_pixel(4 + 24 + 5, image_y + 5).0,
+        [50, 50, 50, 255]
+    );
+    assert_eq!(
+        sheet.get_pixel(4, image_y + 2 * (cell_height + 4)).0,
+        [200, 200, 200, 255]
+    );
+
+    // the label is drawn in the bar
+    let label = image::imageops::crop_imm(&sheet, 4, 4, 20, sheet::label_height()).to_image();
+    assert!(label.pixels().any(|pixel| pixel.0 == [255, 255, 255, 255]));
+}
+
+#[test]
+fn gif_has_a_frame_per_image() {
+    let images = images();
+
+    let mut bytes = Vec::new();
+    sheet::write_gif(&mut bytes, &images, Duration::from_millis(500)).unwrap();
+
+    let frames = GifDecoder::new(std::io::Cursor::new(bytes))
+        .unwrap()
+        .into_frames()
+        .collect_frames()
+        .unwrap();
+    assert_eq!(frames.len(), images.len());
+    assert_eq!(
+        frames[0].buffer().dimensions(),
+        (20, 10 + sheet::label_height())
+    );
+    assert_eq!(frames[0].delay().numer_denom_ms(), (500, 1));
+}
@@ -0,0 +1,120 @@
+//! Runs the whole pipeline on images whose edges are known.
+mod common;
+
+use common::view;
+use image::GrayImage;
+use wgpu_canny_edge_detection::{
+    CannyParams, EdgeMasks, apply_canny, create_rgba_texture, metrics, read_luma_image,
+    synthetic::{self, SyntheticImage},
+};
+
+const SIZE: u32 = 96;
+
+/// Detected and real edges may be this far apart, in pixels.
+const TOLERANCE: f32 = 1.5;
+
+fn detect_edges(renderer: &common::TestRenderer, img: &SyntheticImage) -> GrayImage {
+    let input = create_rgba_texture(renderer, &img.image);
+    let edges = apply_canny(
+        renderer,
+        view(&input),
+        &CannyParams::default(),
+        None,
+        &EdgeMasks::default(),
+    )
+    .unwrap();
+
+    read_luma_image(renderer, &edges)
+}
+
+/// Removes the border, where the filters create edges because they leave their apron zero.
+fn without_border(edges: &GrayImage) -> GrayImage {
+    let margin = CannyParams::default().apron() + 2;
+
+    image::imageops::crop_imm(
+        edges,
+        margin,
+        margin,
+        edges.width() - 2 * margin,
+        edges.height() - 2 * margin,
+    )
+    .to_image()
+}
+
+/// Panics if too many detected edges are wrong (precision) or too many real edges are missing (recall).
+fn assert_edges_match(name: &str, img: &SyntheticImage, min_precision: f32, min_recall: f32) {
+    let renderer = renderer_or_skip!();
+
+    let detected = without_border(&detect_edges(&renderer, img));
+    let metrics = metrics::evaluate(&detected, &without_border(&img.edges), TOLERANCE);
+
+    assert!(
+        metrics.precision >= min_precision && metrics.recall >= min_recall,
+        "{name}: precision {:.3} (at least {min_precision}), recall {:.3} (at least {min_recall})",
+        metrics.precision,
+        metrics.recall
+    );
+}
+
+#[test]
+fn uniform_image_has_no_edges() {
+    let renderer = renderer_or_skip!();
+
+    let edges = detect_edges(&renderer, &synthetic::pattern(SIZE, SIZE, |_, _| false));
+
+    assert!(without_border(&edges).pixels().all(|pixel| pixel.0[0] == 0));
+}
+
+#[test]
+fn step_edges() {
+    for degrees in [0., 20., 45., 90., 110., 135., 160.] {
+        let angle = f32::to_radians(degrees);
+        let img = synthetic::step_edge(SIZE, SIZE, [48.3, 47.6], angle);
+
+        assert_edges_match(&format!("{degrees}°"), &img, 1., 1.);
+    }
+}
+
+#[test]
+fn circle() {
+    let img = synthetic::circle(SIZE, SIZE, [48.2, 47.7], 24.3);
+
+    assert_edges_match("circle", &img, 1., 1.);
+}
+
+#[test]
+fn ramp() {
+    let img = synthetic::ramp(SIZE, SIZE, 48.3, 4.);
+
+    assert_edges_match("ramp", &img, 1., 1.);
+}
+
+#[test]
+fn checkerboard() {
+    let square_size = 16.;
+    // shifts the grid lines away from the pixel borders
+    let offset = 0.3;
+
+    let img = synthetic::pattern(SIZE, SIZE, |x, y| {
+        let column = ((x - offset) / square_size).floor();
+        let row = ((y - offset) / square_size).floor();
+        (column + row).rem_euclid(2.) == 0.
+    });
+
+    // canny rounds off the corners
+    assert_edges_match("checkerboard", &img, 0.95, 0.95);
+}
+
+#[test]
+fn text() {
+    let img = synthetic::text(SIZE * 2, SIZE * 2, 40, 1);
+
+    assert_edges_match("text", &img, 0.9, 0.9);
+}
+
+#[test]
+fn noisy_step_edge() {
+    let img = synthetic::step_edge(SIZE, SIZE, [48.3, 47.6], 0.3).with_noise(8., 1);
+
+    assert_edges_match("noisy step edge", &img, 0.9, 0.9);
+}
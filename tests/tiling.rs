@@ -0,0 +1,65 @@
+mod common;
+
+use common::view;
+use image::DynamicImage;
+use wgpu_canny_edge_detection::{
+    CannyError, CannyParams, EdgeMasks, apply_canny, apply_canny_tiled, create_image_texture,
+    read_texture, synthetic,
+};
+
+const WIDTH: u32 = 100;
+const HEIGHT: u32 = 90;
+const TILE_SIZE: u32 = 32;
+
+#[test]
+fn tiles_match_whole_image() {
+    let renderer = renderer_or_skip!();
+    let params = CannyParams::default();
+
+    // a circle and a tilted line which cross the seams of the tiles, the noise adds weak edges
+    let img = synthetic::pattern(WIDTH, HEIGHT, |x, y| {
+        (x - 40.3).hypot(y - 45.6) < 25. || x + 0.4 * y > 85.
+    })
+    .with_noise(6., 1)
+    .image;
+    let img = DynamicImage::ImageRgba8(img);
+
+    let input = create_image_texture(&renderer, &img);
+    let whole: Vec<u8> = read_texture(
+        &renderer,
+        &apply_canny(
+            &renderer,
+            view(&input),
+            &params,
+            None,
+            &EdgeMasks::default(),
+        )
+        .unwrap(),
+    )
+    .into_iter()
+    .map(|value| if value >= 1. { u8::MAX } else { 0 })
+    .collect();
+
+    let tiled = apply_canny_tiled(&renderer, &img, &params, TILE_SIZE).unwrap();
+
+    let mismatches: Vec<_> = tiled
+        .enumerate_pixels()
+        .filter(|(x, y, pixel)| pixel.0[0] != whole[(y * WIDTH + x) as usize])
+        .map(|(x, y, _)| (x, y))
+        .collect();
+    assert!(mismatches.is_empty(), "{mismatches:?}");
+    assert!(whole.contains(&u8::MAX));
+}
+
+#[test]
+fn rejects_empty_tiles() {
+    let renderer = renderer_or_skip!();
+
+    let img = DynamicImage::ImageRgba8(synthetic::circle(16, 16, [8., 8.], 4.).image);
+    let result = apply_canny_tiled(&renderer, &img, &CannyParams::default(), 0);
+
+    assert!(
+        matches!(result, Err(CannyError::InvalidParams(_))),
+        "{result:?}"
+    );
+}